@@ -0,0 +1,32 @@
+//! Times `BitVec::count_ones` against a per-byte popcount on a large vector.
+//!
+//! Run with `cargo bench --bench count_ones`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use bitvec_rs::BitVec;
+
+const ITERATIONS: u32 = 1_000;
+
+fn main() {
+    let bytes = (0..1 << 20).map(|i: u32| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect::<Vec<_>>();
+    let vec = BitVec::from_bytes(&bytes);
+
+    let start = Instant::now();
+    let mut words = 0;
+    for _ in 0..ITERATIONS {
+        words = black_box(&vec).count_ones();
+    }
+    let words_time = start.elapsed() / ITERATIONS;
+
+    let start = Instant::now();
+    let mut naive = 0;
+    for _ in 0..ITERATIONS {
+        naive = black_box(&vec).as_bytes().iter().map(|byte| byte.count_ones() as usize).sum::<usize>();
+    }
+    let naive_time = start.elapsed() / ITERATIONS;
+
+    assert_eq!(words, naive);
+    println!("count_ones over {} bits: {:?} per call (per-byte: {:?})", vec.len(), words_time, naive_time);
+}
@@ -0,0 +1,45 @@
+use core::prelude::rust_2021::*;
+
+use super::{BitVec, NotIncreasing};
+
+/// Builds a bit vector from the sorted positions of its set bits, growing the vector to each new
+/// position as it arrives. The built vector ends at the last pushed position.
+#[derive(Clone, Debug, Default)]
+pub struct BitVecBuilder {
+    vec: BitVec,
+    allow_duplicates: bool,
+}
+
+impl BitVecBuilder {
+    /// Constructs a builder that requires strictly increasing positions.
+    pub fn new() -> Self { Self::default() }
+
+    /// Sets whether a position equal to the previous one is accepted (and ignored) rather than
+    /// rejected.
+    pub fn allow_duplicates(mut self, allow: bool) -> Self {
+        self.allow_duplicates = allow;
+        self
+    }
+
+    /// Sets the bit at `position`, which must be greater than the previously pushed position (or
+    /// equal to it if duplicates are allowed). Returns an error and leaves the builder unchanged
+    /// otherwise.
+    ///
+    /// Panics if `position` is `usize::MAX`, since the vector would need `usize::MAX + 1` bits.
+    pub fn push_position(&mut self, position: usize) -> Result<(), NotIncreasing> {
+        if let Some(last) = self.vec.len().checked_sub(1) {
+            if position < last || (position == last && !self.allow_duplicates) {
+                return Err(NotIncreasing { position, last });
+            }
+        }
+        let len = position.checked_add(1)
+            .unwrap_or_else(|| core::panic!("Expected position {} < usize::MAX.", position));
+        self.vec.resize(len, false);
+        unsafe { self.vec.set_unchecked(position, true) };
+        Ok(())
+    }
+
+    /// Consumes the builder and returns the bit vector, of length one past the last pushed
+    /// position, or empty if none were pushed.
+    pub fn build(self) -> BitVec { self.vec }
+}
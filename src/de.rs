@@ -0,0 +1,192 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::prelude::rust_2021::*;
+use alloc::format;
+
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use super::{BitVec, RawVec, bytes_in_bits};
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+use super::Allocator;
+
+const FIELDS: &[&str] = &["nbits", "vec"];
+
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl<'de> Deserialize<'de> for BitVec {
+    /// Deserializes the fields as derived by `Serialize`, rejecting input that breaks the
+    /// invariants of `BitVec`. See `deserialize_with_limit` to also bound the length.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        BitVec::deserialize_with_limit(deserializer, usize::MAX)
+    }
+}
+
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl BitVec {
+    /// Deserializes a `BitVec` of at most `max_bits` bits, for untrusted input. Fails without
+    /// allocating more than `ceil(max_bits / 8)` bytes if the input holds more bits, and fails if
+    /// the number of bytes does not match the number of bits or if the unused bits are not 0.
+    pub fn deserialize_with_limit<'de, D: Deserializer<'de>>(deserializer: D, max_bits: usize)
+        -> Result<Self, D::Error>
+    {
+        let visitor = BitVecVisitor::<RawVec<u8>>::new(max_bits);
+        let (nbits, vec) = deserializer.deserialize_struct("BitVec", FIELDS, visitor)?;
+        Ok(BitVec { nbits, vec })
+    }
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<'de, A: Allocator + Default> Deserialize<'de> for BitVec<A> {
+    /// Deserializes the fields as derived by `Serialize`, rejecting input that breaks the
+    /// invariants of `BitVec`. See `deserialize_with_limit` to also bound the length.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        BitVec::deserialize_with_limit(deserializer, usize::MAX)
+    }
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<A: Allocator + Default> BitVec<A> {
+    /// Deserializes a `BitVec` of at most `max_bits` bits, for untrusted input. Fails without
+    /// allocating more than `ceil(max_bits / 8)` bytes if the input holds more bits, and fails if
+    /// the number of bytes does not match the number of bits or if the unused bits are not 0.
+    pub fn deserialize_with_limit<'de, D: Deserializer<'de>>(deserializer: D, max_bits: usize)
+        -> Result<Self, D::Error>
+    {
+        let visitor = BitVecVisitor::<RawVec<u8, A>>::new(max_bits);
+        let (nbits, vec) = deserializer.deserialize_struct("BitVec", FIELDS, visitor)?;
+        Ok(BitVec { nbits, vec })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum Field { Nbits, Vec }
+
+/// The byte vector of a `BitVec`, into which the bytes are deserialized directly so that they are
+/// allocated only once and by the vector's own allocator.
+trait ByteBuf {
+    fn with_capacity(capacity: usize) -> Self;
+    fn push(&mut self, byte: u8);
+    fn as_slice(&self) -> &[u8];
+}
+
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl ByteBuf for RawVec<u8> {
+    fn with_capacity(capacity: usize) -> Self { RawVec::with_capacity(capacity) }
+    fn push(&mut self, byte: u8) { RawVec::push(self, byte) }
+    fn as_slice(&self) -> &[u8] { self }
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<A: Allocator + Default> ByteBuf for RawVec<u8, A> {
+    fn with_capacity(capacity: usize) -> Self { RawVec::with_capacity_in(capacity, A::default()) }
+    fn push(&mut self, byte: u8) { RawVec::push(self, byte) }
+    fn as_slice(&self) -> &[u8] { self }
+}
+
+struct BitVecVisitor<B> {
+    max_bits: usize,
+    buf: PhantomData<B>,
+}
+
+impl<B: ByteBuf> BitVecVisitor<B> {
+    fn new(max_bits: usize) -> Self { BitVecVisitor { max_bits, buf: PhantomData } }
+
+    fn check_nbits<E: de::Error>(&self, nbits: usize) -> Result<(), E> {
+        if nbits > self.max_bits {
+            return Err(E::custom(format!("Expected #bits {} <= limit {}.", nbits, self.max_bits)));
+        }
+        Ok(())
+    }
+
+    /// Checks the invariants of `BitVec` on its deserialized fields.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn validate<E: de::Error>(nbits: usize, vec: B) -> Result<(usize, B), E> {
+        let bytes = vec.as_slice();
+        if bytes.len() != bytes_in_bits(nbits) {
+            return Err(E::custom(format!(
+                "Expected #bytes {} in vec == ceil(#bits {} / 8).", bytes.len(), nbits)));
+        }
+        if nbits % 8 != 0 && bytes[bytes.len() - 1] >> (nbits % 8) != 0 {
+            return Err(E::custom(format!("Expected the unused bits beyond #bits {} to be 0.", nbits)));
+        }
+        Ok((nbits, vec))
+    }
+}
+
+impl<'de, B: ByteBuf> Visitor<'de> for BitVecVisitor<B> {
+    type Value = (usize, B);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("struct BitVec")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
+        let nbits: usize = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        self.check_nbits(nbits)?;
+        let vec = seq.next_element_seed(LimitedBytes::<B>::new(bytes_in_bits(nbits)))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Self::validate(nbits, vec)
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+        let mut nbits = None;
+        let mut vec = None;
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::Nbits => {
+                    if nbits.is_some() { return Err(de::Error::duplicate_field("nbits")); }
+                    let value = map.next_value()?;
+                    self.check_nbits(value)?;
+                    nbits = Some(value);
+                }
+                Field::Vec => {
+                    if vec.is_some() { return Err(de::Error::duplicate_field("vec")); }
+                    // Before `nbits` is known, only the limit bounds the bytes.
+                    let max = bytes_in_bits(nbits.unwrap_or(self.max_bits));
+                    vec = Some(map.next_value_seed(LimitedBytes::<B>::new(max))?);
+                }
+            }
+        }
+        let nbits = nbits.ok_or_else(|| de::Error::missing_field("nbits"))?;
+        let vec = vec.ok_or_else(|| de::Error::missing_field("vec"))?;
+        Self::validate(nbits, vec)
+    }
+}
+
+/// Deserializes a sequence of at most `max` bytes, failing as soon as it exceeds that.
+struct LimitedBytes<B> {
+    max: usize,
+    buf: PhantomData<B>,
+}
+
+impl<B> LimitedBytes<B> {
+    fn new(max: usize) -> Self { LimitedBytes { max, buf: PhantomData } }
+}
+
+impl<'de, B: ByteBuf> DeserializeSeed<'de> for LimitedBytes<B> {
+    type Value = B;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<B, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, B: ByteBuf> Visitor<'de> for LimitedBytes<B> {
+    type Value = B;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of at most {} bytes", self.max)
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<B, S::Error> {
+        let mut vec = B::with_capacity(seq.size_hint().unwrap_or(0).min(self.max).min(4096));
+        while let Some(byte) = seq.next_element()? {
+            let len = vec.as_slice().len();
+            if len == self.max {
+                return Err(de::Error::invalid_length(len + 1, &self));
+            }
+            vec.push(byte);
+        }
+        Ok(vec)
+    }
+}
@@ -0,0 +1,39 @@
+use core::fmt;
+use core::write;
+use core::prelude::rust_2021::*;
+
+/// Error for an access at an index beyond the end of a bit vector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds {
+    /// The requested index.
+    pub index: usize,
+    /// The length of the bit vector at the time of the access.
+    pub len: usize,
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Index {} out of bounds [0, {})", self.index, self.len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OutOfBounds {}
+
+/// Error for a position pushed to a `BitVecBuilder` that does not follow the previous one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotIncreasing {
+    /// The rejected position.
+    pub position: usize,
+    /// The previously pushed position.
+    pub last: usize,
+}
+
+impl fmt::Display for NotIncreasing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Position {} does not follow previous position {}", self.position, self.last)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotIncreasing {}
@@ -0,0 +1,71 @@
+use core::prelude::rust_2021::*;
+
+use super::{BitSlice, BitVec, SliceIter};
+
+/// Two-dimensional grid of bits stored row by row in a bit vector, so that the bit at row `r` and
+/// column `c` is at index `r * cols + c`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitGrid {
+    vec: BitVec,
+    cols: usize,
+}
+
+impl BitGrid {
+    /// Constructs a grid of `rows` x `cols` cleared bits. Panics if `cols` is 0 or if the grid has
+    /// more than `usize::MAX` bits.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        assert!(cols > 0, "Expected a positive number of columns.");
+        let nbits = rows.checked_mul(cols).unwrap_or_else(
+            || core::panic!("Expected at most usize::MAX bits in a {} x {} grid.", rows, cols));
+        BitGrid { vec: BitVec::from_elem(nbits, false), cols }
+    }
+
+    /// Constructs a grid with rows of `cols` bits from consecutive ranges of `vec`. Panics if
+    /// `cols` is 0 or does not divide the length of `vec`.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn from_bitvec(vec: BitVec, cols: usize) -> Self {
+        assert!(cols > 0, "Expected a positive number of columns.");
+        assert!(vec.len() % cols == 0,
+                "Expected #bits {} to be a multiple of #cols {}.", vec.len(), cols);
+        BitGrid { vec, cols }
+    }
+
+    /// Consumes the grid and returns the underlying bit vector.
+    pub fn into_bitvec(self) -> BitVec { self.vec }
+
+    /// Returns the underlying bit vector.
+    pub fn as_bitvec(&self) -> &BitVec { &self.vec }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize { self.vec.len() / self.cols }
+
+    /// Returns the number of columns.
+    pub fn cols(&self) -> usize { self.cols }
+
+    /// Gets the bit at row `r` and column `c`. Panics if either is out of bounds.
+    pub fn get(&self, r: usize, c: usize) -> bool {
+        let index = self.validate(r, c);
+        unsafe { self.vec.get_unchecked(index) }
+    }
+
+    /// Sets the bit at row `r` and column `c`. Panics if either is out of bounds.
+    pub fn set(&mut self, r: usize, c: usize, value: bool) {
+        let index = self.validate(r, c);
+        unsafe { self.vec.set_unchecked(index, value) }
+    }
+
+    /// Returns an iterator over the bits of row `r`. Panics if `r` is out of bounds.
+    pub fn row_iter(&self, r: usize) -> SliceIter<'_> {
+        assert!(r < self.rows(), "Row {} out of bounds [0, {})", r, self.rows());
+        BitSlice::new(self.vec.as_bytes(), r * self.cols, self.cols).iter()
+    }
+
+    /// Returns the index into the bit vector of row `r` and column `c`, or panics if either is out
+    /// of bounds.
+    fn validate(&self, r: usize, c: usize) -> usize {
+        if r >= self.rows() || c >= self.cols {
+            core::panic!("Index ({}, {}) out of bounds [0, {}) x [0, {})", r, c, self.rows(), self.cols);
+        }
+        r * self.cols + c
+    }
+}
@@ -14,44 +14,73 @@
 
 extern crate alloc;
 
+#[cfg(all(feature = "unstable", feature = "allocator-api2"))]
+compile_error!("The `unstable` and `allocator-api2` features are mutually exclusive.");
+
 #[cfg(feature = "unstable")]
 use core::alloc::Allocator;
+#[cfg(feature = "allocator-api2")]
+use allocator_api2::alloc::{Allocator, Global};
+use core::cmp::Ordering;
 use core::fmt;
 use core::num::Wrapping;
+use core::ops::Range;
 use core::write;
 use core::prelude::rust_2021::*;
+use alloc::string::String;
 use alloc::vec::Vec;
-use alloc::vec;
 #[cfg(feature = "unstable")]
 use alloc::alloc::Global;
 
+// The byte vector underlying `BitVec`, which must support custom allocators on stable Rust with
+// `allocator-api2`.
+#[cfg(not(feature = "allocator-api2"))]
+use alloc::vec::Vec as RawVec;
+#[cfg(feature = "allocator-api2")]
+use allocator_api2::vec::Vec as RawVec;
+#[cfg(not(feature = "allocator-api2"))]
+use alloc::collections::TryReserveError;
+#[cfg(feature = "allocator-api2")]
+use allocator_api2::collections::TryReserveError;
+
 #[cfg(feature = "serde")]
 #[macro_use] extern crate serde;
 
 /// Bit vector with guaranteed `[u8]` LSB 0 representation and safe mutable access to this slice.
 /// Slices into the bit vector are guaranteed to have the unused bits on the last byte set to 0.
-#[cfg(not(feature = "unstable"))]
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 #[derive(Clone, Default, PartialEq, Eq)]
 pub struct BitVec {
     nbits: usize,
-    vec: Vec<u8>,
+    vec: RawVec<u8>,
 }
 
 /// Bit vector with guaranteed `[u8]` LSB 0 representation and safe mutable access to this slice.
 /// Slices into the bit vector are guaranteed to have the unused bits on the last byte set to 0.
-#[cfg(feature = "unstable")]
-#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "")))]
 #[derive(Clone)]
 pub struct BitVec<A: Allocator = Global> {
     nbits: usize,
-    vec: Vec<u8, A>,
+    #[cfg_attr(all(feature = "serde", feature = "unstable"), serde(serialize_with = "serialize_bytes"))]
+    vec: RawVec<u8, A>,
+}
+
+// Serde only implements `Serialize` for `Vec`s with the global allocator, so serialize the bytes
+// as the same sequence that it would produce.
+#[cfg(all(feature = "serde", feature = "unstable"))]
+fn serialize_bytes<A: Allocator, S: serde::Serializer>(vec: &RawVec<u8, A>, serializer: S)
+    -> Result<S::Ok, S::Error>
+{
+    serializer.collect_seq(vec.iter())
 }
 
 // Explicitly allow comparisons between BitVecs regardless of whether
 // they use the same allocator or whether their allocator implements
 // PartialEq or not.
-#[cfg(feature = "unstable")]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 impl<A: Allocator, B: Allocator> PartialEq<BitVec<B>> for BitVec<A> {
     
     fn eq(&self, other: &BitVec<B>) -> bool {
@@ -60,40 +89,79 @@ impl<A: Allocator, B: Allocator> PartialEq<BitVec<B>> for BitVec<A> {
 
 }
 
-#[cfg(feauture = "unstable")]
-impl Default for BitVec {
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<A: Allocator + Default> Default for BitVec<A> {
     
     fn default() -> Self {
-        Self { nbits: 0, vec: Vec::new() }
+        Self::new_in(A::default())
     }
 
 }
 
-#[cfg(feature = "unstable")]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 impl<A: Allocator> Eq for BitVec<A> {}
 
-fn bytes_in_bits(nbits: usize) -> usize {
-    // #bytes = #ceil(nbits / 8)
-    (nbits + 7) / 8
+// Guarantee that bit vectors and their iterators and views can be shared across threads. With a
+// custom allocator, these follow the allocator's `Send` and `Sync`.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<BitVec>();
+    assert_send_sync::<Iter<'_>>();
+    assert_send_sync::<IntoIter>();
+    assert_send_sync::<BitSlice<'_>>();
+};
+
+/// The ratio of capacity to length beyond which `pop_and_maybe_shrink` releases spare capacity.
+pub const POP_SHRINK_RATIO: usize = 4;
+
+#[allow(clippy::manual_is_multiple_of)]
+const fn bytes_in_bits(nbits: usize) -> usize {
+    // #bytes = #ceil(nbits / 8), which unlike `(nbits + 7) / 8` cannot overflow.
+    nbits / 8 + (nbits % 8 != 0) as usize
+}
+
+/// Returns the index of the `k`th of `sample_bytes` evenly spaced samples among `whole_bytes` bytes,
+/// computed in `u128` since the product of the counts can overflow `usize`.
+fn sample_index(k: usize, whole_bytes: usize, sample_bytes: usize) -> usize {
+    (k as u128 * whole_bytes as u128 / sample_bytes as u128) as usize
 }
 
 fn byte_from_bool(bit: bool) -> u8 {
     if bit { !0u8 } else { 0u8 }
 }
 
-#[cfg(feature = "unstable")]
+/// Returns the value of the ASCII hexadecimal digit `c`, of either case.
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|digit| digit as u8)
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 impl<A: Allocator> BitVec<A> {
     ////////////////////////////////////////
     // Constructors
 
     /// Constructs an empty `BitVec`.
     pub const fn new_in(alloc: A) -> Self {
-        Self { vec: Vec::new_in(alloc), nbits: 0 }
+        Self { vec: RawVec::new_in(alloc), nbits: 0 }
     }
 
     /// Constructs a `BitVec` from bytes.
     pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
-        Self { vec: Vec::with_capacity_in(bytes_in_bits(capacity), alloc), nbits: 0 }
+        Self { vec: RawVec::with_capacity_in(bytes_in_bits(capacity), alloc), nbits: 0 }
+    }
+
+    /// Constructs a `BitVec` from bytes.
+    pub fn from_bytes_in(bytes: &[u8], alloc: A) -> Self {
+        let mut vec = RawVec::with_capacity_in(bytes.len(), alloc);
+        vec.extend_from_slice(bytes);
+        Self { vec, nbits: bytes.len() * 8 }
+    }
+
+    /// Constructs a `BitVec` from a repeating bit value.
+    pub fn from_elem_in(len: usize, value: bool, alloc: A) -> Self {
+        let mut vec = Self::with_capacity_in(len, alloc);
+        vec.push_repeated(len, value);
+        vec
     }
 
 }
@@ -104,7 +172,21 @@ impl BitVec {
 
     /// Constructs an empty `BitVec`.
     pub const fn new() -> Self {
-        Self { vec: Vec::new(), nbits: 0 }
+        Self { vec: RawVec::new(), nbits: 0 }
+    }
+
+    /// Returns the number of bytes needed to hold `nbits` bits, i.e. `ceil(nbits / 8)` computed
+    /// without overflow. This is the length of `as_bytes` for a `BitVec` of length `nbits`.
+    pub const fn bytes_needed(nbits: usize) -> usize {
+        bytes_in_bits(nbits)
+    }
+
+    /// Returns `index` with its low `bits` bits in reverse order, ignoring any higher bits, such as
+    /// for the bit-reversal permutation of a radix-2 FFT. Panics if `bits` exceeds `usize::BITS`.
+    pub const fn bit_reverse_index(index: usize, bits: u32) -> usize {
+        assert!(bits <= usize::BITS, "Expected at most usize::BITS bits to reverse.");
+        if bits == 0 { return 0 }
+        index.reverse_bits() >> (usize::BITS - bits)
     }
 
     /// Constructs an empty `BitVec` with the given capacity.
@@ -112,12 +194,29 @@ impl BitVec {
     /// The bit vector will be able to hold at least capacity bits without reallocating. If
     /// capacity is 0, the bit vector will not allocate.
     pub fn with_capacity(capacity: usize) -> Self {
-        Self { vec: Vec::with_capacity(bytes_in_bits(capacity)), nbits: 0 }
+        Self { vec: RawVec::with_capacity(bytes_in_bits(capacity)), nbits: 0 }
     }
 
     /// Constructs a `BitVec` from bytes.
     pub fn from_bytes(bytes: &[u8]) -> Self {
-        let mut vec = Self { vec: Vec::from(bytes), nbits: bytes.len() * 8 };
+        let mut vec = Self { vec: RawVec::from(bytes), nbits: bytes.len() * 8 };
+        vec.set_unused_zero();
+        vec
+    }
+
+    /// Constructs a `BitVec` from bytes in the big-endian order of `to_be_bytes`, i.e. from the
+    /// bytes of `as_bytes` in reverse order. The length is 8 times the number of bytes.
+    pub fn from_be_bytes(bytes: &[u8]) -> Self {
+        bytes.iter().rev().copied().collect()
+    }
+
+    /// Constructs a `BitVec` of length `nbits` from the leading bytes of `bytes`, ignoring any
+    /// further bits. Panics if `bytes` holds fewer than `nbits` bits.
+    pub fn from_bytes_with_len(bytes: &[u8], nbits: usize) -> Self {
+        let nbytes = bytes_in_bits(nbits);
+        assert!(nbytes <= bytes.len(),
+                "Expected #bits {} <= 8 x (#bytes {} in slice).", nbits, bytes.len());
+        let mut vec = Self { vec: RawVec::from(&bytes[..nbytes]), nbits };
         vec.set_unused_zero();
         vec
     }
@@ -133,10 +232,361 @@ impl BitVec {
 
     /// Constructs a `BitVec` from a repeating bit value.
     pub fn from_elem(len: usize, value: bool) -> Self {
-        let mut vec = Self {
-            vec: vec![byte_from_bool(value); bytes_in_bits(len)],
-            nbits: len,
+        let mut vec = Self::with_capacity(len);
+        vec.push_repeated(len, value);
+        vec
+    }
+
+    /// Constructs a `BitVec` from its run-length encoding as `(value, length)` pairs.
+    pub fn from_rle(runs: &[(bool, usize)]) -> Self {
+        let mut vec = Self::with_capacity(runs.iter().map(|&(_, len)| len).sum());
+        for &(value, len) in runs {
+            vec.push_repeated(len, value);
+        }
+        vec
+    }
+
+    /// Constructs a `BitVec` of length `len` with the bits set at the delta-encoded indices
+    /// `deltas`, inverting `set_positions_delta`. Panics if an index is out of bounds.
+    pub fn from_set_positions_delta(len: usize, deltas: &[usize]) -> Self {
+        let mut vec = Self::from_elem(len, false);
+        let mut pos = 0usize;
+        for &delta in deltas {
+            pos = pos.saturating_add(delta);
+            vec.set(pos, true);
+        }
+        vec
+    }
+
+    /// Constructs a `BitVec` of length `nbits` from the low `nbits` bits of `value`, with bit 0 the
+    /// least significant. Panics if `nbits > 64`.
+    pub fn from_u64(value: u64, nbits: usize) -> Self {
+        assert!(nbits <= 64, "Expected at most 64 bits, got {}.", nbits);
+        let mut vec = Self::with_capacity(nbits);
+        vec.push_bits(value, nbits as u32);
+        vec
+    }
+
+    /// Constructs a `BitVec` holding the Elias gamma codes of `values` in sequence, each written
+    /// as `k` zeros followed by the `k + 1` significant bits of the value, most significant first.
+    /// The code cannot represent 0, so panics if any value is 0.
+    pub fn encode_elias_gamma(values: &[u32]) -> Self {
+        let mut vec = Self::new();
+        for &value in values {
+            assert!(value >= 1, "Expected Elias gamma values >= 1, got {}.", value);
+            let nbits = 32 - value.leading_zeros();
+            vec.push_repeated(nbits as usize - 1, false);
+            // `push_bits` writes least significant first, so reverse to emit the value MSB first.
+            vec.push_bits((value.reverse_bits() >> (32 - nbits)) as u64, nbits);
+        }
+        vec
+    }
+
+    ////////////////////////////////////////
+    // Splitting
+
+    /// Splits the bit vector in two at `at`, leaving the bits in `[0, at)` in `self` and returning
+    /// the bits in `[at, len)`, like `Vec::split_off`. Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> BitVec {
+        assert!(at <= self.nbits, "Split index {} out of bounds [0, {}]", at, self.nbits);
+        let tail = self.iter().skip(at).collect();
+        self.truncate(at);
+        tail
+    }
+
+    ////////////////////////////////////////
+    // Streams
+
+    /// Returns a writer that appends bits in sequence to the end of the `BitVec`.
+    pub fn bit_writer(&mut self) -> BitWriter<'_> {
+        BitWriter::new(self)
+    }
+
+    ////////////////////////////////////////
+    // Transformations
+
+    /// Interleaves the bits of `self` and `other` (Morton order) into a vector of twice the length
+    /// whose even indices come from `self` and odd indices from `other`. Panics if the lengths
+    /// differ.
+    pub fn interleave(&self, other: &BitVec) -> BitVec {
+        assert!(self.len() == other.len(),
+                "Expected equal lengths to interleave, got {} and {}.", self.len(), other.len());
+        let mut vec = BitVec::with_capacity(2 * self.len());
+        for (a, b) in self.iter().zip(other.iter()) {
+            vec.push(a);
+            vec.push(b);
+        }
+        vec
+    }
+
+    /// Splits the bits at even and odd indices into two vectors, inverting `interleave`. Panics if
+    /// the length is odd.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn deinterleave(&self) -> (BitVec, BitVec) {
+        assert!(self.len() % 2 == 0, "Expected an even length to deinterleave, got {}.", self.len());
+        let mut even = BitVec::with_capacity(self.len() / 2);
+        let mut odd = BitVec::with_capacity(self.len() / 2);
+        let mut iter = self.iter();
+        while let (Some(a), Some(b)) = (iter.next(), iter.next()) {
+            even.push(a);
+            odd.push(b);
+        }
+        (even, odd)
+    }
+
+    /// Treats the bits as an unsigned integer with bit 0 least significant and returns its
+    /// reflected Gray code, `self ^ (self >> 1)`, of the same length.
+    pub fn to_gray(&self) -> BitVec {
+        let vec = self.vec.iter().enumerate().map(|(index, &byte)| {
+            let next = self.vec.get(index + 1).copied().unwrap_or(0);
+            byte ^ ((byte >> 1) | (next << 7))
+        }).collect();
+        BitVec { nbits: self.nbits, vec }
+    }
+
+    /// Treats the bits as a reflected Gray code with bit 0 least significant and returns the
+    /// unsigned integer it encodes, inverting `to_gray`.
+    pub fn from_gray(&self) -> BitVec {
+        let mut vec = BitVec::from_elem(self.len(), false);
+        let mut bit = false;
+        for index in (0..self.len()).rev() {
+            bit ^= self[index];
+            unsafe { vec.set_unchecked(index, bit) };
+        }
+        vec
+    }
+
+    /// Returns a copy with the bits in reverse order, so that bit `i` of the result is bit
+    /// `len - 1 - i` of `self`.
+    pub fn reversed(&self) -> BitVec {
+        self.iter().rev().collect()
+    }
+
+    /// Returns a copy with a `!stuff` bit inserted after every `run` consecutive `stuff` bits, as
+    /// in HDLC framing with `run = 5, stuff = true`. Panics if `run` is 0.
+    pub fn stuff_bits(&self, run: usize, stuff: bool) -> BitVec {
+        assert!(run > 0, "Expected a positive run length to stuff.");
+        let mut vec = BitVec::with_capacity(self.len() + self.len() / run);
+        let mut count = 0;
+        for bit in self.iter() {
+            vec.push(bit);
+            count = if bit == stuff { count + 1 } else { 0 };
+            if count == run {
+                vec.push(!stuff);
+                count = 0;
+            }
+        }
+        vec
+    }
+
+    /// Returns a copy with the bit following every `run` consecutive `stuff` bits removed,
+    /// inverting `stuff_bits`. Panics if `run` is 0.
+    pub fn destuff_bits(&self, run: usize, stuff: bool) -> BitVec {
+        assert!(run > 0, "Expected a positive run length to destuff.");
+        let mut vec = BitVec::with_capacity(self.len());
+        let mut count = 0;
+        let mut iter = self.iter();
+        while let Some(bit) = iter.next() {
+            vec.push(bit);
+            count = if bit == stuff { count + 1 } else { 0 };
+            if count == run {
+                iter.next();
+                count = 0;
+            }
+        }
+        vec
+    }
+
+    /// Treats the `n` bytes as the rows of an `n`x8 bit matrix and returns its transpose: the 8
+    /// bitplanes of `n` bits each, where bit `i` of plane `j`, i.e. bit `j * n + i`, is bit `j` of
+    /// byte `i`. For 8 bytes, this transposes an 8x8 bit matrix and is its own inverse. Panics if
+    /// the length is not a multiple of 8.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn transpose8(&self) -> BitVec {
+        assert!(self.len() % 8 == 0, "Expected a multiple of 8 bits to transpose, got {}.", self.len());
+        let n = self.vec.len();
+        let mut out = BitVec::from_elem(self.nbits, false);
+        let vec = &mut out.vec;
+        // Ors in `byte` at bit offset `offset`, which need not be byte-aligned. Only the bits that
+        // spill over are written to the next byte, which may be past the end if there are none.
+        let mut or_byte = |offset: usize, byte: u8| {
+            vec[offset / 8] |= byte << (offset % 8);
+            if offset % 8 != 0 && byte >> (8 - offset % 8) != 0 {
+                vec[offset / 8 + 1] |= byte >> (8 - offset % 8);
+            }
         };
+        let blocks = self.vec.chunks_exact(8);
+        let tail = blocks.remainder();
+        for (b, block) in blocks.enumerate() {
+            // Bit `8 * i + j` holds row `i`, column `j`; swap across the diagonal in three steps.
+            let mut x = u64::from_le_bytes(block.try_into().unwrap());
+            let t = (x ^ (x >> 7)) & 0x00aa_00aa_00aa_00aa;
+            x ^= t ^ (t << 7);
+            let t = (x ^ (x >> 14)) & 0x0000_cccc_0000_cccc;
+            x ^= t ^ (t << 14);
+            let t = (x ^ (x >> 28)) & 0x0000_0000_f0f0_f0f0;
+            x ^= t ^ (t << 28);
+            // Byte `j` now holds the bits of plane `j` for the block's 8 rows.
+            for (j, &byte) in x.to_le_bytes().iter().enumerate() {
+                or_byte(j * n + 8 * b, byte);
+            }
+        }
+        let tail_start = n - tail.len();
+        for (i, &byte) in tail.iter().enumerate() {
+            for j in 0..8 {
+                or_byte(j * n + tail_start + i, (byte >> j) & 1);
+            }
+        }
+        out
+    }
+
+    /// Returns the bitwise majority vote of `a`, `b`, and `c`: each bit is set iff it is set in at
+    /// least two of the inputs. Panics if the lengths differ.
+    pub fn majority3(a: &BitVec, b: &BitVec, c: &BitVec) -> BitVec {
+        assert!(a.len() == b.len() && b.len() == c.len(),
+                "Expected equal lengths to vote, got {}, {}, and {}.", a.len(), b.len(), c.len());
+        let vec = a.vec.iter().zip(&b.vec).zip(&c.vec)
+            .map(|((&a, &b), &c)| (a & b) | (b & c) | (a & c))
+            .collect();
+        BitVec { nbits: a.nbits, vec }
+    }
+
+    ////////////////////////////////////////
+    // Interchange
+
+    /// Returns the portable Roaring bitmap serialization of the indices of the set bits. Panics if
+    /// an index of a set bit does not fit in a `u32`.
+    #[cfg(feature = "roaring")]
+    pub fn to_roaring_bytes(&self) -> Vec<u8> {
+        let mut bitmap = roaring::RoaringBitmap::new();
+        for (index, value) in self.iter_indexed() {
+            if value {
+                let index = u32::try_from(index)
+                    .unwrap_or_else(|_| core::panic!("Expected set bit index {} to fit in a u32.", index));
+                bitmap.push(index);
+            }
+        }
+        let mut bytes = Vec::with_capacity(bitmap.serialized_size());
+        bitmap.serialize_into(&mut bytes).expect("Writing to a Vec cannot fail.");
+        bytes
+    }
+
+    /// Constructs a `BitVec` of length `len` with the bits set at the indices in the portable
+    /// Roaring bitmap serialization `bytes`. Fails if `bytes` is malformed or holds an index
+    /// `>= len`.
+    #[cfg(feature = "roaring")]
+    pub fn from_roaring_bytes(bytes: &[u8], len: usize) -> std::io::Result<Self> {
+        let bitmap = roaring::RoaringBitmap::deserialize_from(bytes)?;
+        let mut vec = Self::from_elem(len, false);
+        for index in bitmap {
+            if index as usize >= len {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData,
+                                               format!("Index {} out of bounds [0, {})", index, len)));
+            }
+            unsafe { vec.set_unchecked(index as usize, true) };
+        }
+        Ok(vec)
+    }
+
+    /// Constructs a `BitVec` from hexadecimal text, each pair of digits giving the next byte of
+    /// `as_bytes`, so the length is 4 times the number of digits. Returns `None` if `hex` contains a
+    /// non-hex character or an odd number of digits.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.len() % 2 != 0 { return None }
+        hex.as_bytes().chunks_exact(2)
+            .map(|pair| Some(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+            .collect::<Option<Vec<u8>>>()
+            .map(|bytes| Self::from_bytes(&bytes))
+    }
+
+    /// Like `from_hex`, but reads the hexadecimal text from `reader` in bounded chunks rather than
+    /// holding it all in memory. Fails with `InvalidData` on a non-hex character or an odd number
+    /// of digits, and passes on any error from `reader`.
+    #[cfg(feature = "std")]
+    pub fn from_hex_reader<R: std::io::Read>(mut reader: R) -> std::io::Result<Self> {
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let mut vec = Self::new();
+        let mut buf = [0u8; 4096];
+        let mut high = None;
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            for &c in &buf[..n] {
+                let digit = hex_digit(c)
+                    .ok_or_else(|| invalid(format!("Expected a hex digit, got {:?}.", c as char)))?;
+                match high.take() {
+                    None => high = Some(digit),
+                    Some(high) => {
+                        vec.vec.push(high << 4 | digit);
+                        vec.nbits += 8;
+                    }
+                }
+            }
+        }
+        if high.is_some() {
+            return Err(invalid(format!("Expected an even number of hex digits, got {}.", vec.len() / 4 + 1)));
+        }
+        Ok(vec)
+    }
+
+    ////////////////////////////////////////
+    // Set operations
+    //
+    // Unlike bitwise operations on protocol fields, these accept operands of different lengths,
+    // treating each as the set of indices of its set bits.
+
+    /// Returns the bitwise OR, with the length of the longer operand. The shorter operand is
+    /// treated as if extended with 0s, so the result is the union of the two sets.
+    pub fn union_grow(&self, other: &BitVec) -> BitVec {
+        self.zip_grow(other, |a, b| a | b)
+    }
+
+    /// Returns the bitwise XOR, with the length of the longer operand. The shorter operand is
+    /// treated as if extended with 0s, so the result is the symmetric difference of the two sets.
+    pub fn xor_grow(&self, other: &BitVec) -> BitVec {
+        self.zip_grow(other, |a, b| a ^ b)
+    }
+
+    /// Returns the bitwise AND, with the length of the shorter operand. The bits of the longer
+    /// operand beyond that length are dropped, which loses nothing since they cannot be in the
+    /// intersection.
+    pub fn intersect_trunc(&self, other: &BitVec) -> BitVec {
+        let vec = self.vec.iter().zip(&other.vec).map(|(&a, &b)| a & b).collect();
+        BitVec { nbits: self.nbits.min(other.nbits), vec }
+    }
+
+    /// Combines the bytes of the longer of `self` and `other` with those of the shorter using `f`,
+    /// where `f(byte, 0) == byte` so that the zeroed unused bits stay zero.
+    fn zip_grow<F: Fn(u8, u8) -> u8>(&self, other: &BitVec, f: F) -> BitVec {
+        let (long, short) = if self.nbits >= other.nbits { (self, other) } else { (other, self) };
+        let mut vec = long.clone();
+        for (a, &b) in vec.vec.iter_mut().zip(&short.vec) {
+            *a = f(*a, b);
+        }
+        vec
+    }
+
+    ////////////////////////////////////////
+    // Arithmetic
+
+    /// Treats both bit vectors as unsigned integers with bit 0 least significant and returns their
+    /// sum, truncated to the same length. Panics if the lengths differ.
+    pub fn wrapping_add(&self, other: &BitVec) -> BitVec {
+        assert!(self.len() == other.len(),
+                "Expected equal lengths to add, got {} and {}.", self.len(), other.len());
+        let mut carry = false;
+        let vec = self.vec.iter().zip(&other.vec).map(|(&a, &b)| {
+            let (sum, carry1) = a.overflowing_add(b);
+            let (sum, carry2) = sum.overflowing_add(carry as u8);
+            carry = carry1 || carry2;
+            sum
+        }).collect();
+        let mut vec = BitVec { nbits: self.nbits, vec };
         vec.set_unused_zero();
         vec
     }
@@ -152,7 +602,28 @@ macro_rules! impl_bitvec {
         /// Returns a byte slice view of the data.
         pub fn as_bytes(&self) -> &[u8] { &self.vec }
 
-        /// Invokes the given function on a mut byte slice view of the data. After `f` completes, the
+        /// Returns an iterator over consecutive `N`-byte arrays of the byte view of the data,
+        /// omitting the trailing `len % N` bytes, which are returned by `array_chunks_remainder`.
+        /// If the length is not a multiple of 8, the last byte includes its unused 0 bits. Panics
+        /// if `N` is 0.
+        pub fn array_chunks<const N: usize>(&self) -> impl Iterator<Item = [u8; N]> + '_ {
+            self.vec.chunks_exact(N).map(|chunk| chunk.try_into().unwrap())
+        }
+
+        /// Returns the trailing bytes omitted by `array_chunks::<N>`, fewer than `N` of them.
+        /// Panics if `N` is 0.
+        pub fn array_chunks_remainder<const N: usize>(&self) -> &[u8] {
+            self.vec.chunks_exact(N).remainder()
+        }
+
+        /// Returns an iterator over the bytes of the data, along with the number of valid bits in
+        /// the last byte, which is 0 if the last byte is full or if the vector is empty.
+        pub fn bytes_with_tail(&self) -> (impl Iterator<Item = u8> + '_, u32) {
+            (self.vec.iter().copied(), (self.nbits % 8) as u32)
+        }
+
+        /// Invokes the given function on a mut byte slice view of the data. The slice holds exactly
+        /// the `ceil(self.len()/8)` used bytes, with no spare capacity. After `f` completes, the
         /// trailing unused bits of the last byte are automatically set to 0.
         pub fn with_bytes_mut<U, F: FnOnce(&mut [u8]) -> U>(&mut self, f: F) -> U {
             let val = f(&mut self.vec);
@@ -160,11 +631,60 @@ macro_rules! impl_bitvec {
             val
         }
 
+        /// Returns a raw pointer to the `ceil(self.len()/8)` bytes of the data in LSB 0 order.
+        pub fn as_ptr(&self) -> *const u8 { self.vec.as_ptr() }
+
+        /// Returns a raw mutable pointer to the `ceil(self.len()/8)` bytes of the data in LSB 0
+        /// order. Writes through it may set the trailing unused bits of the last byte, which must
+        /// then be restored to 0 with `set_unused_zero` before using any other method.
+        pub fn as_mut_ptr(&mut self) -> *mut u8 { self.vec.as_mut_ptr() }
+
         /// Consumes the `self` and returns the underlying `Vec<u8>` of length `ceil(self.len()/8)`.
         /// The values of the bits in the last byte of `Vec<u8>` beyond the length of the `BitVec` are
         /// 0.
         pub fn into_bytes(self) -> $into_bytes_type { self.vec }
 
+        /// Constructs a `BitVec` of length `nbits` directly from its underlying byte vector without
+        /// copying.
+        ///
+        /// # Safety
+        ///
+        /// `vec.len()` must equal `ceil(nbits/8)`, and the bits of the last byte beyond `nbits`
+        /// must be 0. Other methods rely on both to stay memory-safe and return correct results.
+        pub unsafe fn from_raw_parts(vec: $into_bytes_type, nbits: usize) -> Self {
+            Self { vec, nbits }
+        }
+
+        /// Consumes `self` and returns the underlying `Vec<u8>` along with the number of bits.
+        /// See `into_bytes` for the layout.
+        pub fn into_raw_parts(self) -> ($into_bytes_type, usize) { (self.vec, self.nbits) }
+
+        /// Returns the bits as a `Vec<bool>`.
+        pub fn to_bool_vec(&self) -> Vec<bool> {
+            let mut bools = Vec::with_capacity(self.nbits);
+            bools.extend(self.iter());
+            bools
+        }
+
+        /// Consumes `self` and returns the bits as a `Vec<bool>`.
+        pub fn into_bool_vec(self) -> Vec<bool> { self.to_bool_vec() }
+
+        /// Returns the bytes in big-endian order, treating the bits as an unsigned integer with bit
+        /// 0 least significant. This is `as_bytes` with the byte order reversed and the bit order
+        /// within each byte unchanged: output byte `k` holds bits `[8(n - 1 - k), 8(n - k))` of the
+        /// `n` bytes, with the lowest-indexed of them as its least significant bit. The most
+        /// significant bit of the first output byte is thus the last bit, or a 0 padding bit if the
+        /// length is not a multiple of 8.
+        pub fn to_be_bytes(&self) -> Vec<u8> {
+            self.vec.iter().rev().copied().collect()
+        }
+
+        /// Returns the bits as an array of `N` booleans, or `None` if the length is not `N`.
+        pub fn to_bool_array<const N: usize>(&self) -> Option<[bool; N]> {
+            if self.nbits != N { return None }
+            Some(core::array::from_fn(|index| unsafe { self.get_unchecked(index) }))
+        }
+
         ////////////////////////////////////////
         // Getters/setters
 
@@ -174,11 +694,33 @@ macro_rules! impl_bitvec {
         /// Returns whether the vector is empty.
         pub fn is_empty(&self) -> bool { self.nbits == 0 }
 
+        /// Panics in debug builds if the byte length does not match the bit length or the unused
+        /// bits of the last byte are not 0. Call this after unsafe writes, e.g. in tests, to catch
+        /// a missing `set_unused_zero`.
+        pub fn debug_assert_invariants(&self) {
+            debug_assert!(self.vec.len() == bytes_in_bits(self.nbits),
+                          "Expected #bytes {} in vec == ceil(#bits {} / 8).", self.vec.len(), self.nbits);
+            debug_assert!(self.nbits % 8 == 0 || self.vec[self.vec.len() - 1] >> (self.nbits % 8) == 0,
+                          "Expected the unused bits beyond #bits {} to be 0.", self.nbits);
+        }
+
         /// Validates the index for validity or panics.
         fn validate_index(&self, index: usize) {
             assert!(self.nbits <= self.vec.len() * 8,
                     "Expected #bits {} <= 8 x (#bytes {} in vec).", self.nbits, self.vec.len());
-            if index >= self.nbits { panic!("Index {} out of bounds [0, {})", index, self.nbits); }
+            self.check_index(index);
+        }
+
+        /// Panics if the index is out of bounds, without re-checking the invariants.
+        fn check_index(&self, index: usize) {
+            if index >= self.nbits { core::panic!("Index {} out of bounds [0, {})", index, self.nbits); }
+        }
+
+        /// Validates the range for validity or panics.
+        fn validate_range(&self, range: &Range<usize>) {
+            if range.start > range.end || range.end > self.nbits {
+                core::panic!("Range [{}, {}) out of bounds [0, {})", range.start, range.end, self.nbits);
+            }
         }
 
         /// Gets the bit at the given `index`.
@@ -190,25 +732,164 @@ macro_rules! impl_bitvec {
             }
         }
 
+        /// Gets the bit at the given `index`, or an error describing the out-of-bounds access.
+        pub fn try_get(&self, index: usize) -> Result<bool, OutOfBounds> {
+            self.get(index).ok_or(OutOfBounds { index, len: self.nbits })
+        }
+
+        /// Gets the bits at each of the given `indices`, or `None` if any index exceeds length.
+        pub fn get_many<const N: usize>(&self, indices: [usize; N]) -> Option<[bool; N]> {
+            if indices.iter().any(|&index| index >= self.nbits) { return None }
+            Some(indices.map(|index| unsafe { self.get_unchecked(index) }))
+        }
+
+        /// Reads the `n` bits starting at `start` as an integer whose least significant bit is the
+        /// bit at `start`, the inverse of `push_bits`. Panics if `n > 64` or the bits are out of
+        /// bounds.
+        pub fn extract_u64(&self, start: usize, n: u32) -> u64 {
+            assert!(n <= 64, "Expected at most 64 bits to extract, got {}.", n);
+            self.validate_range(&(start..start.saturating_add(n as usize)));
+            let mut value = 0u64;
+            for i in 0..n {
+                if unsafe { self.get_unchecked(start + i as usize) } {
+                    value |= 1u64 << i;
+                }
+            }
+            value
+        }
+
+        /// Reads the `n` bits starting at `start` as an integer whose most significant bit is the
+        /// bit at `start`, the inverse of `push_bits_msb0`. This is `extract_u64` with the order of
+        /// the `n` bits reversed. Panics if `n > 64` or the bits are out of bounds.
+        pub fn extract_u64_msb0(&self, start: usize, n: u32) -> u64 {
+            let value = self.extract_u64(start, n);
+            if n == 0 { 0 } else { value.reverse_bits() >> (64 - n) }
+        }
+
         /// Sets the bit at the given `index`. Panics if `index` exceeds length.
         pub fn set(&mut self, index: usize, value: bool) {
             self.validate_index(index);
             unsafe { self.set_unchecked(index, value) };
         }
 
-        /// Swaps two elements in the `BitVec`.
+        /// Sets each bit at the given `indices` to `value`. Panics, before modifying any bits, if
+        /// any index exceeds length.
+        pub fn set_indices(&mut self, indices: &[usize], value: bool) {
+            for &index in indices {
+                self.validate_index(index);
+            }
+            for &index in indices {
+                unsafe { self.set_unchecked(index, value) };
+            }
+        }
+
+        /// Clears the bits in `range` to 0, writing only the bytes that hold them. Panics if `range`
+        /// is out of bounds.
+        pub fn clear_range(&mut self, range: Range<usize>) {
+            self.validate_range(&range);
+            if range.start == range.end { return }
+            let (first, last) = (range.start / 8, range.end / 8);
+            // Masks of the bits in a byte below `range.start` and below `range.end`, respectively.
+            let keep_low = (1u8 << (range.start % 8)) - 1;
+            let clear_low = (1u8 << (range.end % 8)) - 1;
+            if first == last {
+                self.vec[first] &= keep_low | !clear_low;
+                return;
+            }
+            self.vec[first] &= keep_low;
+            for byte in &mut self.vec[first + 1..last] { *byte = 0; }
+            if range.end % 8 != 0 {
+                self.vec[last] &= !clear_low;
+            }
+        }
+
+        /// Flips the bits in `range`, writing only the bytes that hold them. Panics if `range` is
+        /// out of bounds.
+        pub fn toggle_range(&mut self, range: Range<usize>) {
+            self.validate_range(&range);
+            if range.start == range.end { return }
+            let (first, last) = (range.start / 8, range.end / 8);
+            // Masks of the bits in a byte at or above `range.start` and below `range.end`.
+            let high = !((1u8 << (range.start % 8)) - 1);
+            let low = (1u8 << (range.end % 8)) - 1;
+            if first == last {
+                self.vec[first] ^= high & low;
+                return;
+            }
+            self.vec[first] ^= high;
+            for byte in &mut self.vec[first + 1..last] { *byte ^= 0xff; }
+            if range.end % 8 != 0 {
+                self.vec[last] ^= low;
+            }
+            self.set_unused_zero();
+        }
+
+        /// Returns the bits that differ from `base`, i.e. `self ^ base`, to store `self` as a diff
+        /// against `base`. Applying the diff to `base` with `apply_xor_diff` recovers `self`.
+        /// Panics if the lengths differ.
+        pub fn xor_diff(&self, base: &Self) -> Self where Self: Clone {
+            let mut diff = self.clone();
+            diff ^= base;
+            diff
+        }
+
+        /// Flips the bits set in `diff`, i.e. `self ^= diff`, inverting `xor_diff`: for bit vectors
+        /// `a` and `base` of equal length, `base` with `a.xor_diff(&base)` applied equals `a`.
+        /// Panics if the lengths differ.
+        pub fn apply_xor_diff(&mut self, diff: &Self) {
+            *self ^= diff;
+        }
+
+        /// Swaps two elements in the `BitVec`. Panics if either index is out of bounds.
         pub fn swap(&mut self, i: usize, j: usize) {
             self.validate_index(i);
-            self.validate_index(j);
-            unsafe {
-                let val_i = self.get_unchecked(i);
-                let val_j = self.get_unchecked(j);
-                self.set_unchecked(i, val_j);
-                self.set_unchecked(j, val_i);
+            self.check_index(j);
+            unsafe { self.swap_unchecked(i, j) };
+        }
+
+        /// Swaps two elements in the `BitVec`, or returns an error for the first out-of-bounds
+        /// index without changing anything.
+        pub fn try_swap(&mut self, i: usize, j: usize) -> Result<(), OutOfBounds> {
+            for index in [i, j] {
+                if index >= self.nbits {
+                    return Err(OutOfBounds { index, len: self.nbits });
+                }
             }
+            unsafe { self.swap_unchecked(i, j) };
+            Ok(())
+        }
+
+        /// Reorders the bits so that the bit at each index moves to the index with its low `bits`
+        /// bits reversed (see `bit_reverse_index`). Applying this twice restores the original order.
+        /// Panics if the length is not `2^bits`.
+        pub fn permute_bit_reversed(&mut self, bits: u32) {
+            assert!(1usize.checked_shl(bits) == Some(self.nbits),
+                    "Expected #bits {} == 2^{}.", self.nbits, bits);
+            for i in 0..self.nbits {
+                let j = BitVec::bit_reverse_index(i, bits);
+                if i < j {
+                    unsafe { self.swap_unchecked(i, j) };
+                }
+            }
+        }
+
+        /// Swaps two elements in the `BitVec` without bounds checking.
+        ///
+        /// # Safety
+        ///
+        /// `i` and `j` must both be less than `self.len()`.
+        pub unsafe fn swap_unchecked(&mut self, i: usize, j: usize) {
+            let val_i = self.get_unchecked(i);
+            let val_j = self.get_unchecked(j);
+            self.set_unchecked(i, val_j);
+            self.set_unchecked(j, val_i);
         }
 
         /// Gets the bit at the given `index` without bounds checking.
+        ///
+        /// # Safety
+        ///
+        /// `index` must be less than `self.len()`.
         pub unsafe fn get_unchecked(&self, index: usize) -> bool {
             let byte = self.vec.get_unchecked(index / 8);
             let pattern = 1u8 << (index % 8);
@@ -216,6 +897,10 @@ macro_rules! impl_bitvec {
         }
 
         /// Sets the bit at the given `index` without bounds checking.
+        ///
+        /// # Safety
+        ///
+        /// `index` must be less than `self.len()`.
         pub unsafe fn set_unchecked(&mut self, index: usize, value: bool) {
             let byte = self.vec.get_unchecked_mut(index / 8);
             let pattern = 1u8 << (index % 8);
@@ -237,6 +922,47 @@ macro_rules! impl_bitvec {
             self.nbits += 1;
         }
 
+        /// Pushes a boolean to the end of the `BitVec`, returning an error instead of panicking or
+        /// aborting if a new byte is needed and cannot be allocated. Pushing into the unused bits
+        /// of the last byte never allocates, so always succeeds.
+        pub fn try_push(&mut self, value: bool) -> Result<(), TryReserveError> {
+            if self.nbits % 8 == 0 {
+                self.vec.try_reserve(1)?;
+            }
+            self.push(value);
+            Ok(())
+        }
+
+        /// Moves all the bits of `other` onto the end of `self`, leaving `other` empty.
+        pub fn append(&mut self, other: &mut Self) {
+            self.append_bytes(&other.vec, other.nbits);
+            other.clear();
+        }
+
+        /// Appends a copy of the bits, doubling the length.
+        pub fn double(&mut self) {
+            let bytes = self.vec.to_vec();
+            self.append_bytes(&bytes, self.nbits);
+        }
+
+        /// Appends the `n` low bits of `value`, least significant first. Panics if `n > 64`.
+        pub fn push_bits(&mut self, value: u64, n: u32) {
+            assert!(n <= 64, "Expected at most 64 bits to push, got {}.", n);
+            self.reserve(n as usize);
+            for i in 0..n {
+                self.push((value >> i) & 1 == 1);
+            }
+        }
+
+        /// Appends the `n` low bits of `value`, most significant first, e.g. as in big-endian
+        /// fields. This is `push_bits` with the order of the `n` bits reversed. Panics if `n > 64`.
+        pub fn push_bits_msb0(&mut self, value: u64, n: u32) {
+            assert!(n <= 64, "Expected at most 64 bits to push, got {}.", n);
+            if n > 0 {
+                self.push_bits(value.reverse_bits() >> (64 - n), n);
+            }
+        }
+
          /// Pops a boolean from the end of the `BitVec`.
         pub fn pop(&mut self) -> Option<bool> {
             if self.nbits == 0 { return None }
@@ -258,6 +984,34 @@ macro_rules! impl_bitvec {
             Some(value)
         }
 
+        /// Like `pop`, but then releases the spare capacity if the capacity exceeds
+        /// `POP_SHRINK_RATIO` times the length, bounding the memory held by a vector that grows and
+        /// shrinks repeatedly.
+        pub fn pop_and_maybe_shrink(&mut self) -> Option<bool> {
+            let value = self.pop();
+            if self.nbits * POP_SHRINK_RATIO < self.capacity() {
+                self.shrink_to_fit();
+            }
+            value
+        }
+
+        /// Appends a byte's bits in LSB 0 order to the end of the `BitVec`. Panics if the length
+        /// is not a multiple of 8.
+        pub fn push_byte(&mut self, byte: u8) {
+            assert!(self.nbits % 8 == 0, "Expected #bits {} to be byte-aligned.", self.nbits);
+            self.vec.push(byte);
+            self.nbits += 8;
+        }
+
+        /// Pops the last 8 bits from the end of the `BitVec` as a byte in LSB 0 order. Returns
+        /// `None` if the vector is empty and panics if the length is not a multiple of 8.
+        pub fn pop_byte(&mut self) -> Option<u8> {
+            assert!(self.nbits % 8 == 0, "Expected #bits {} to be byte-aligned.", self.nbits);
+            let byte = self.vec.pop()?;
+            self.nbits -= 8;
+            Some(byte)
+        }
+
         /// Clears the `BitVec`, removing all values.
         pub fn clear(&mut self) {
             self.vec.clear();
@@ -269,15 +1023,76 @@ macro_rules! impl_bitvec {
             self.vec.capacity() * 8
         }
 
+        /// Returns the number of bytes allocated on the heap, including spare capacity. This
+        /// excludes the `BitVec` itself, which holds the length and the `Vec<u8>` header.
+        pub fn heap_size(&self) -> usize {
+            self.vec.capacity()
+        }
+
+        /// Returns the number of bytes holding bits, i.e. `ceil(self.len()/8)`.
+        pub fn used_bytes(&self) -> usize {
+            bytes_in_bits(self.nbits)
+        }
+
         /// Reserves capacity for at least additional more booleans to be inserted in the given
         /// `BitVec`. The collection may reserve more space to avoid frequent reallocations.
         pub fn reserve(&mut self, additional: usize) {
             self.vec.reserve(bytes_in_bits(additional))
         }
 
+        /// Shrinks the capacity as much as possible, to the bytes needed to hold `len` bits.
+        pub fn shrink_to_fit(&mut self) {
+            self.vec.shrink_to_fit()
+        }
+
+        /// Removes the first `n` bits, shifting the rest down in place. Clears the vector if `n`
+        /// exceeds its length.
+        pub fn truncate_front(&mut self, n: usize) {
+            if n == 0 { return }
+            if n >= self.nbits {
+                self.clear();
+                return;
+            }
+            let (skip, shift) = (n / 8, n % 8);
+            let new_len = self.nbits - n;
+            for i in 0..bytes_in_bits(new_len) {
+                let mut byte = self.vec[i + skip] >> shift;
+                if shift != 0 && i + skip + 1 < self.vec.len() {
+                    byte |= self.vec[i + skip + 1] << (8 - shift);
+                }
+                self.vec[i] = byte;
+            }
+            self.nbits = new_len;
+            self.vec.truncate(bytes_in_bits(new_len));
+            self.set_unused_zero();
+        }
+
+        /// Tries to reserve capacity for at least `additional` more booleans, returning an error
+        /// instead of panicking or aborting if the capacity overflows or allocation fails.
+        pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+            self.vec.try_reserve(bytes_in_bits(additional))
+        }
+
+        /// Sets the length to `nbits` without touching the data, such as after writing the bytes
+        /// into reserved capacity through `as_mut_ptr`.
+        ///
+        /// # Safety
+        ///
+        /// - `bytes_in_bits(nbits)`, i.e. `ceil(nbits/8)`, must not exceed the byte capacity
+        ///   `self.capacity() / 8`.
+        /// - Each of the first `ceil(nbits/8)` bytes must be initialized.
+        /// - The bits of the last byte beyond `nbits` must be 0.
+        ///
+        /// Other methods rely on these to stay memory-safe and return correct results.
+        pub unsafe fn set_len(&mut self, nbits: usize) {
+            self.vec.set_len(bytes_in_bits(nbits));
+            self.nbits = nbits;
+        }
+
         /// Shorten a vector, dropping excess elements.
         ///
-        /// If `len` is greater than the vector's current length, this has no effect.
+        /// If `len` is greater than the vector's current length, this has no effect. The allocated
+        /// capacity is kept for reuse; see `shrink_to_fit`.
         pub fn truncate(&mut self, len: usize) {
             if len < self.len() {
             let nbytes = bytes_in_bits(len);
@@ -287,8 +1102,9 @@ macro_rules! impl_bitvec {
             }
         }
 
-        /// Reserves capacity for at least additional more booleans to be inserted in the given
-        /// `BitVec`. The collection may reserve more space to avoid frequent reallocations.
+        /// Resizes the `BitVec` in-place so that `len` is equal to `new_len`, filling any new bits
+        /// with `value`. Like `truncate`, shrinking keeps the allocated capacity for reuse; see
+        /// `resize_and_shrink` to release it.
         pub fn resize(&mut self, new_len: usize, value: bool) {
             if new_len > self.len() {
                 let additional = new_len - self.len();
@@ -301,95 +1117,711 @@ macro_rules! impl_bitvec {
             }
         }
 
+        /// Resizes like `resize` and then releases any excess capacity like `shrink_to_fit`.
+        pub fn resize_and_shrink(&mut self, new_len: usize, value: bool) {
+            self.resize(new_len, value);
+            self.shrink_to_fit();
+        }
 
-        ////////////////////////////////////////
-        // Helpers
+        /// Resizes the `BitVec` in-place so that `len` is equal to `new_len`, filling any new bits
+        /// with the values returned by successive calls to `f`.
+        pub fn resize_with<F: FnMut() -> bool>(&mut self, new_len: usize, mut f: F) {
+            if new_len > self.len() {
+                let additional = new_len - self.len();
+                self.reserve(additional);
+                for _ in 0..additional {
+                    self.push(f());
+                }
+            } else {
+                self.truncate(new_len);
+            }
+        }
 
-        /// Sets the extra unused bits in the bitvector to 0.
-        fn set_unused_zero(&mut self) {
-            if self.nbits % 8 == 0 { return }
-            let len = self.vec.len(); // avoid mutable borrow error
-            assert!(len > 0);
+        /// Appends `n` copies of `value`. Equivalent to `resize(len + n, value)`, under the name
+        /// used by the `bit-vec` crate.
+        pub fn grow(&mut self, n: usize, value: bool) {
+            self.reserve(n);
+            self.push_repeated(n, value);
+        }
 
-            let byte = unsafe { self.vec.get_unchecked_mut(len - 1) };
-            // Pattern with all 1's in the used bits only, avoiding overflow check in debug.
-            let pattern = (Wrapping(1u8 << (self.nbits % 8)) - Wrapping(1u8)).0;
-            *byte &= pattern;
+        /// Extends to `new_len` bits by appending 0s, preserving the value of the bits as an
+        /// unsigned integer with bit 0 least significant. Panics if `new_len < len`.
+        pub fn zero_extend(&mut self, new_len: usize) {
+            assert!(new_len >= self.nbits, "Expected length {} >= {} to extend to.", new_len, self.nbits);
+            self.grow(new_len - self.nbits, false);
         }
-    }
-}
 
-#[cfg(not(feature = "unstable"))]
-impl BitVec {
-    impl_bitvec!(Vec<u8>);
+        /// Extends to `new_len` bits by repeating the last bit, preserving the value of the bits as
+        /// a two's-complement integer with bit 0 least significant. An empty vector is extended
+        /// with 0s. Panics if `new_len < len`.
+        pub fn sign_extend(&mut self, new_len: usize) {
+            assert!(new_len >= self.nbits, "Expected length {} >= {} to extend to.", new_len, self.nbits);
+            let sign = self.nbits > 0 && unsafe { self.get_unchecked(self.nbits - 1) };
+            self.grow(new_len - self.nbits, sign);
+        }
+
+        /// Replaces the bits in `range` with the bits of `replacement`, shifting the bits after
+        /// `range` to follow it. Panics if `range` is out of bounds.
+        pub fn splice(&mut self, range: Range<usize>, replacement: &Self) {
+            self.validate_range(&range);
+            let old_len = self.nbits;
+            let new_end = range.start + replacement.len();
+            if new_end > range.end {
+                // Grow first, then shift the tail right starting from its end so no bit is
+                // overwritten before it is moved.
+                self.grow(new_end - range.end, false);
+                for i in (range.end..old_len).rev() {
+                    unsafe { self.set_unchecked(i - range.end + new_end, self.get_unchecked(i)) };
+                }
+            } else if new_end < range.end {
+                for i in range.end..old_len {
+                    unsafe { self.set_unchecked(i - range.end + new_end, self.get_unchecked(i)) };
+                }
+                self.truncate(old_len - (range.end - new_end));
+            }
+            self.copy_bits_from(range.start, replacement, 0..replacement.len());
+        }
+
+        /// Inserts the bits of `other` starting at `index`, shifting the bits from `index` on up by
+        /// `other.len()`. Panics if `index > len`.
+        pub fn insert_bitvec(&mut self, index: usize, other: &Self) {
+            assert!(index <= self.nbits, "Index {} out of bounds [0, {}]", index, self.nbits);
+            self.splice(index..index, other);
+        }
+
+        /// Removes the bits in `range`, shifting the bits after it down to `range.start`, like
+        /// `Vec::drain` without yielding the removed values. Panics if `range` is out of bounds.
+        pub fn remove_range(&mut self, range: Range<usize>) {
+            self.validate_range(&range);
+            if range.start == range.end { return }
+            let old_len = self.nbits;
+            for i in range.end..old_len {
+                unsafe { self.set_unchecked(i - range.end + range.start, self.get_unchecked(i)) };
+            }
+            self.truncate(old_len - (range.end - range.start));
+        }
+
+        /// Collapses each run of identical consecutive bits into a single bit, like `Vec::dedup`.
+        pub fn dedup(&mut self) {
+            if self.nbits < 2 { return }
+            let mut len = 1;
+            for i in 1..self.nbits {
+                unsafe {
+                    let value = self.get_unchecked(i);
+                    if value != self.get_unchecked(len - 1) {
+                        self.set_unchecked(len, value);
+                        len += 1;
+                    }
+                }
+            }
+            self.truncate(len);
+        }
+
+        /// Retains only the bits whose index `i` satisfies `f(i)`, preserving their order.
+        pub fn retain_indices<F: FnMut(usize) -> bool>(&mut self, mut f: F) {
+            let mut len = 0;
+            for i in 0..self.nbits {
+                if f(i) {
+                    unsafe { self.set_unchecked(len, self.get_unchecked(i)) };
+                    len += 1;
+                }
+            }
+            self.truncate(len);
+        }
+
+        /// Overwrites the bits starting at `dest_start` with the bits of `src` in `src_range`.
+        /// Panics if either range is out of bounds.
+        pub fn copy_bits_from(&mut self, dest_start: usize, src: &Self, src_range: Range<usize>) {
+            src.validate_range(&src_range);
+            let len = src_range.end - src_range.start;
+            self.validate_range(&(dest_start..dest_start.saturating_add(len)));
+            for offset in 0..len {
+                unsafe {
+                    let value = src.get_unchecked(src_range.start + offset);
+                    self.set_unchecked(dest_start + offset, value);
+                }
+            }
+        }
+
+        /// Formats the bits from index 0 using `set` and `clear` for each bit, with a space between
+        /// each group of `group` bits. A `group` of 0 disables grouping. `Display` is equivalent to
+        /// `format_bits(8, '1', '.')`.
+        pub fn format_bits(&self, group: usize, set: char, clear: char) -> String {
+            let mut s = String::with_capacity(self.nbits + if group > 0 { self.nbits / group } else { 0 });
+            write_bits(&mut s, self.into_iter(), group, set, clear).unwrap();
+            s
+        }
+
+        /// Returns the CRC-32 (ISO-HDLC, as used by zlib and Ethernet) of the bytes from `as_bytes`.
+        /// When the length is not a multiple of 8, this includes the zeroed unused bits of the last
+        /// byte, so vectors differing only in trailing 0 bits, e.g. of lengths 5 and 8, have equal
+        /// CRCs.
+        #[cfg(feature = "crc")]
+        pub fn crc32(&self) -> u32 {
+            const CRC32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+            CRC32.checksum(&self.vec)
+        }
+
+        /// Returns a fast non-cryptographic hash (64-bit FNV-1a) of the length and bits, as a hint
+        /// for detecting changes. Equal bit vectors have equal fingerprints, but unequal ones may
+        /// collide.
+        pub fn fingerprint(&self) -> u64 {
+            const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+            const PRIME: u64 = 0x0000_0100_0000_01b3;
+            let len = (self.nbits as u64).to_le_bytes();
+            len.iter().chain(self.vec.iter()).fold(OFFSET_BASIS, |hash, &byte| {
+                (hash ^ byte as u64).wrapping_mul(PRIME)
+            })
+        }
+
+        ////////////////////////////////////////
+        // Bit counting
+
+        /// Returns the number of set bits.
+        pub fn count_ones(&self) -> usize {
+            // Counting whole `u64` words is faster, and the zero padding bits don't contribute.
+            let words = self.vec.chunks_exact(8);
+            let tail = words.remainder().iter().map(|byte| byte.count_ones() as usize).sum::<usize>();
+            words.map(|word| u64::from_ne_bytes(word.try_into().unwrap()).count_ones() as usize)
+                .sum::<usize>() + tail
+        }
+
+        /// Returns the number of bits set in both `self` and `mask`, i.e. `(self & mask).count_ones()`
+        /// without allocating. Panics if the lengths differ.
+        pub fn count_ones_masked(&self, mask: &Self) -> usize {
+            assert!(self.len() == mask.len(),
+                    "Expected equal lengths to mask, got {} and {}.", self.len(), mask.len());
+            self.vec.iter().zip(mask.vec.iter()).map(|(&a, &b)| (a & b).count_ones() as usize).sum()
+        }
+
+        /// Returns the number of consecutive set bits starting from index 0.
+        pub fn trailing_ones(&self) -> usize {
+            let mut count = 0;
+            for &byte in self.vec.iter() {
+                let ones = byte.trailing_ones() as usize;
+                count += ones;
+                if ones < 8 { break; }
+            }
+            // The unused bits are 0, so they never extend the count past `nbits`.
+            count
+        }
+
+        /// Returns the number of consecutive set bits ending at the last index.
+        pub fn leading_ones(&self) -> usize {
+            let mut bytes = self.vec.iter().rev();
+            let used = self.nbits % 8;
+            if used != 0 {
+                // Shift out the unused 0 bits so the last bit is the most significant.
+                let ones = (bytes.next().unwrap() << (8 - used)).leading_ones() as usize;
+                if ones < used { return ones; }
+            }
+            let mut count = used;
+            for &byte in bytes {
+                let ones = byte.leading_ones() as usize;
+                count += ones;
+                if ones < 8 { break; }
+            }
+            count
+        }
+
+        /// Estimates the fraction of set bits by counting those in `sample_bytes` evenly spaced
+        /// whole bytes, or returns the exact fraction if there are at most `sample_bytes` whole
+        /// bytes. Returns 0 if the vector is empty. Panics if `sample_bytes` is 0.
+        pub fn approximate_density(&self, sample_bytes: usize) -> f64 {
+            assert!(sample_bytes > 0, "Expected a positive number of bytes to sample.");
+            if self.nbits == 0 { return 0.0 }
+            // Only sample whole bytes so that the padding bits don't skew the estimate.
+            let whole_bytes = self.nbits / 8;
+            if whole_bytes <= sample_bytes {
+                return self.count_ones() as f64 / self.nbits as f64;
+            }
+            let ones: usize = (0..sample_bytes)
+                .map(|k| self.vec[sample_index(k, whole_bytes, sample_bytes)].count_ones() as usize)
+                .sum();
+            ones as f64 / (sample_bytes * 8) as f64
+        }
+
+        /// Returns the XOR of all bits, i.e. whether an odd number of bits is set.
+        pub fn parity(&self) -> bool {
+            let folded = self.vec.iter().fold(0u8, |acc, &byte| acc ^ byte);
+            folded.count_ones() % 2 == 1
+        }
+
+        /// Returns the XOR of the bits in `range`. Panics if `range` is out of bounds.
+        pub fn parity_in(&self, range: Range<usize>) -> bool {
+            self.validate_range(&range);
+            range.fold(false, |acc, index| acc ^ unsafe { self.get_unchecked(index) })
+        }
+
+        /// Returns the number of set bits in each byte of `as_bytes`, in order.
+        pub fn byte_popcounts(&self) -> Vec<u8> {
+            self.vec.iter().map(|byte| byte.count_ones() as u8).collect()
+        }
+
+        /// Returns the number of adjacent pairs of bits that differ, i.e. the number of boundaries
+        /// between runs.
+        pub fn count_transitions(&self) -> usize {
+            let mut count = 0;
+            let mut carry = 0u8;
+            for (index, &byte) in self.vec.iter().enumerate() {
+                // Bit `i` of `diff` is set if bit `i` differs from the bit before it.
+                let mut diff = byte ^ ((byte << 1) | carry);
+                if index == 0 { diff &= !1; }
+                if index == self.vec.len() - 1 && self.nbits % 8 != 0 {
+                    // Ignore the boundary into the zeroed unused bits.
+                    diff &= (1u8 << (self.nbits % 8)) - 1;
+                }
+                count += diff.count_ones() as usize;
+                carry = byte >> 7;
+            }
+            count
+        }
+
+        /// Returns the Jaccard similarity `|self & other| / |self | other|` of the sets of indices of
+        /// set bits, or 1.0 if neither has any bits set. Panics if the lengths differ.
+        pub fn jaccard(&self, other: &Self) -> f64 {
+            assert!(self.len() == other.len(),
+                    "Expected equal lengths to compare, got {} and {}.", self.len(), other.len());
+            let (mut intersection, mut union) = (0usize, 0usize);
+            for (&a, &b) in self.vec.iter().zip(other.vec.iter()) {
+                intersection += (a & b).count_ones() as usize;
+                union += (a | b).count_ones() as usize;
+            }
+            if union == 0 { 1.0 } else { intersection as f64 / union as f64 }
+        }
+
+        ////////////////////////////////////////
+        // Searching
+
+        /// Returns the 0-based position of the `n`-th set bit, counting from 0, or `None` if
+        /// there are fewer than `n + 1` set bits.
+        pub fn nth_one(&self, n: usize) -> Option<usize> {
+            let mut remaining = n;
+            for (index, &byte) in self.vec.iter().enumerate() {
+                let ones = byte.count_ones() as usize;
+                if remaining < ones {
+                    // Clear the lowest `remaining` set bits; the lowest bit left is the target.
+                    let mut byte = byte;
+                    for _ in 0..remaining { byte &= byte - 1; }
+                    return Some(index * 8 + byte.trailing_zeros() as usize);
+                }
+                remaining -= ones;
+            }
+            None
+        }
+
+        /// Returns the number of leading bits that are equal in `self` and `other`, at most the
+        /// length of the shorter one.
+        pub fn common_prefix_len(&self, other: &Self) -> usize {
+            let len = self.nbits.min(other.nbits);
+            for (index, (&a, &b)) in self.vec.iter().zip(other.vec.iter()).enumerate() {
+                if a != b {
+                    return len.min(index * 8 + (a ^ b).trailing_zeros() as usize);
+                }
+            }
+            len
+        }
+
+        /// Returns whether `self` and `other` agree on all bits up to the length of the shorter one,
+        /// i.e. whether one is a prefix of the other. Unlike `==`, the lengths may differ.
+        pub fn eq_prefix(&self, other: &Self) -> bool {
+            self.common_prefix_len(other) == self.nbits.min(other.nbits)
+        }
+
+        /// Returns the start index and length of the longest run of consecutive bits equal to
+        /// `value`, preferring the earliest on ties, or `(0, 0)` if no bit equals `value`.
+        pub fn longest_run(&self, value: bool) -> (usize, usize) {
+            let mut longest = (0, 0);
+            let mut start = 0;
+            for (run_value, len) in self.runs() {
+                if run_value == value && len > longest.1 {
+                    longest = (start, len);
+                }
+                start += len;
+            }
+            longest
+        }
+
+        ////////////////////////////////////////
+        // Arithmetic
+
+        /// Adds one to the bits treated as an unsigned integer with bit 0 least significant,
+        /// wrapping around to 0 on overflow. Returns whether it wrapped.
+        pub fn increment(&mut self) -> bool {
+            let mut carry = true;
+            for byte in self.vec.iter_mut() {
+                let (sum, overflow) = byte.overflowing_add(1);
+                *byte = sum;
+                carry = overflow;
+                if !carry { break; }
+            }
+            // A carry out of the top bit of a partial last byte lands in its unused bits.
+            if self.nbits % 8 != 0 && self.vec[self.vec.len() - 1] >> (self.nbits % 8) != 0 {
+                carry = true;
+                self.set_unused_zero();
+            }
+            carry
+        }
+
+        /// Compares the bits as unsigned integers with bit 0 least significant, unlike the
+        /// lexicographic order by index. Panics if the lengths differ.
+        pub fn cmp_as_uint(&self, other: &Self) -> Ordering {
+            assert!(self.len() == other.len(),
+                    "Expected equal lengths to compare, got {} and {}.", self.len(), other.len());
+            // The unused bits are 0, so the bytes compare as base-256 digits from the top.
+            self.vec.iter().rev().cmp(other.vec.iter().rev())
+        }
+
+        /// Returns the bits as an integer with bit 0 least significant, or `None` if there are more
+        /// than 64 bits.
+        pub fn to_u64(&self) -> Option<u64> {
+            if self.nbits > 64 { return None }
+            let mut bytes = [0u8; 8];
+            bytes[..self.vec.len()].copy_from_slice(&self.vec);
+            Some(u64::from_le_bytes(bytes))
+        }
+
+        /// Returns the indices of the set bits in increasing order, delta-encoded: the first entry
+        /// is the index of the first set bit and each later entry is the gap from the previous one.
+        pub fn set_positions_delta(&self) -> Vec<usize> {
+            let mut deltas = Vec::new();
+            let mut prev = 0;
+            for (index, &byte) in self.vec.iter().enumerate() {
+                let mut byte = byte;
+                while byte != 0 {
+                    let pos = index * 8 + byte.trailing_zeros() as usize;
+                    deltas.push(pos - prev);
+                    prev = pos;
+                    byte &= byte - 1;
+                }
+            }
+            deltas
+        }
+
+        /// Returns the indices, in increasing order, at which `self` and `other` differ. Panics if
+        /// the lengths differ.
+        pub fn changed_positions(&self, other: &Self) -> Vec<usize> {
+            assert!(self.len() == other.len(),
+                    "Expected equal lengths to compare, got {} and {}.", self.len(), other.len());
+            let mut positions = Vec::new();
+            for (index, (&a, &b)) in self.vec.iter().zip(other.vec.iter()).enumerate() {
+                let mut byte = a ^ b;
+                while byte != 0 {
+                    positions.push(index * 8 + byte.trailing_zeros() as usize);
+                    byte &= byte - 1;
+                }
+            }
+            positions
+        }
+
+        ////////////////////////////////////////
+        // Bitwise operations into an output
+
+        /// Writes the bitwise AND of `self` and `rhs` into `out`, reusing its allocation rather
+        /// than allocating a new `BitVec` like `&`. Panics if the lengths of `self` and `rhs` differ.
+        pub fn and_into(&self, rhs: &Self, out: &mut Self) {
+            self.bitwise_into(rhs, out, "and", |a, b| a & b);
+        }
+
+        /// Writes the bitwise OR of `self` and `rhs` into `out`, reusing its allocation rather than
+        /// allocating a new `BitVec` like `|`. Panics if the lengths of `self` and `rhs` differ.
+        pub fn or_into(&self, rhs: &Self, out: &mut Self) {
+            self.bitwise_into(rhs, out, "or", |a, b| a | b);
+        }
+
+        /// Writes the bitwise XOR of `self` and `rhs` into `out`, reusing its allocation rather
+        /// than allocating a new `BitVec` like `^`. Panics if the lengths of `self` and `rhs` differ.
+        pub fn xor_into(&self, rhs: &Self, out: &mut Self) {
+            self.bitwise_into(rhs, out, "xor", |a, b| a ^ b);
+        }
+
+        ////////////////////////////////////////
+        // Codes
+
+        /// Decodes a sequence of Elias gamma codes written by `encode_elias_gamma`. Panics if the
+        /// bits end partway through a codeword or a codeword does not fit in a `u32`.
+        pub fn decode_elias_gamma(&self) -> Vec<u32> {
+            let mut values = Vec::new();
+            let mut reader = self.bit_reader();
+            while reader.remaining() > 0 {
+                let start = reader.position();
+                let mut zeros = 0u32;
+                let mut bit = reader.read_bit();
+                while bit == Some(false) {
+                    zeros += 1;
+                    bit = reader.read_bit();
+                }
+                assert!(bit.is_some() && zeros as usize <= reader.remaining(),
+                        "Expected a complete Elias gamma codeword at bit {}.", start);
+                assert!(zeros < 32, "Expected an Elias gamma codeword fitting in 32 bits at bit {}.", start);
+                let mut value = 1u32;
+                for _ in 0..zeros {
+                    value = (value << 1) | reader.read_bit().unwrap() as u32;
+                }
+                values.push(value);
+            }
+            values
+        }
+
+        ////////////////////////////////////////
+        // Views
+
+        /// Returns an immutable view of all the bits, for passing to code that accepts a `BitSlice`.
+        ///
+        /// This is not offered through `Deref` since a `BitSlice` is a borrowing value rather than
+        /// an unsized type that `&self` could be reinterpreted as.
+        pub fn as_bit_slice(&self) -> BitSlice<'_> {
+            BitSlice::new(&self.vec, 0, self.nbits)
+        }
+
+        /// Returns an iterator over the maximal runs of identical bits, yielding `(value, length)`
+        /// pairs in order from index 0.
+        pub fn runs(&self) -> Runs<'_> {
+            Runs::new(self.as_bit_slice())
+        }
+
+        /// Returns an iterator over the bits paired with their indices, starting from index 0.
+        pub fn iter_indexed(&self) -> impl DoubleEndedIterator<Item = (usize, bool)> + ExactSizeIterator + '_ {
+            (0..self.nbits).zip(self.iter())
+        }
+
+        /// Returns an iterator over the pairs of adjacent bits `(bit[i], bit[i + 1])` for `i` in
+        /// `[0, len - 1)`, like `slice::windows(2)`. It is empty if the length is less than 2.
+        pub fn bit_pairs(&self) -> impl Iterator<Item = (bool, bool)> + '_ {
+            self.iter().zip(self.iter().skip(1))
+        }
+
+        /// Returns an iterator over successive `width`-bit integers as read by `extract_u64`, the
+        /// inverse of repeated `push_bits(.., width)`. The final `len % width` bits, too few for a
+        /// symbol, are skipped; read them with `extract_u64` if needed. Panics unless
+        /// `1 <= width <= 64`.
+        pub fn symbols(&self, width: u32) -> impl Iterator<Item = u64> + '_ {
+            assert!((1..=64).contains(&width), "Expected a symbol width in [1, 64], got {}.", width);
+            let width = width as usize;
+            (0..self.nbits / width).map(move |i| self.extract_u64(i * width, width as u32))
+        }
+
+        /// Returns the run-length encoding of the bits as `(value, length)` pairs.
+        pub fn to_rle(&self) -> Vec<(bool, usize)> {
+            self.runs().collect()
+        }
+
+        /// Divides the bit vector into two immutable views at `mid`, the first containing the bits
+        /// in `[0, mid)` and the second those in `[mid, len)`. Panics if `mid > len`.
+        pub fn split_at(&self, mid: usize) -> (BitSlice<'_>, BitSlice<'_>) {
+            assert!(mid <= self.nbits, "Split index {} out of bounds [0, {}]", mid, self.nbits);
+            (BitSlice::new(&self.vec, 0, mid), BitSlice::new(&self.vec, mid, self.nbits - mid))
+        }
+
+        /// Returns a reader of the bits in sequence from index 0.
+        pub fn bit_reader(&self) -> BitReader<'_> {
+            BitReader::new(self.as_bit_slice())
+        }
+
+        /// Returns a reader of consecutive fields of bits as new bit vectors, from index 0.
+        pub fn field_reader(&self) -> FieldReader<'_> {
+            FieldReader::new(self.as_bit_slice())
+        }
+
+        ////////////////////////////////////////
+        // Helpers
+
+        /// Replaces the contents of `out` with `op` applied to each pair of bytes of `self` and
+        /// `rhs`. `op` must map two 0 bits to a 0 bit to keep the unused bits 0.
+        fn bitwise_into<F: Fn(u8, u8) -> u8>(&self, rhs: &Self, out: &mut Self, name: &str, op: F) {
+            assert!(self.len() == rhs.len(),
+                    "Expected equal lengths to {}, got {} and {}.", name, self.len(), rhs.len());
+            out.vec.clear();
+            out.vec.extend(self.vec.iter().zip(rhs.vec.iter()).map(|(&a, &b)| op(a, b)));
+            out.nbits = self.nbits;
+        }
+
+        /// Appends the `nbits` bits of `bytes`, whose unused bits must be 0.
+        fn append_bytes(&mut self, bytes: &[u8], nbits: usize) {
+            let shift = self.nbits % 8;
+            if shift == 0 {
+                self.vec.extend_from_slice(bytes);
+            } else {
+                // Split each byte across the partial last byte and a new byte. The unused bits of
+                // `bytes` are 0, so any excess new byte is 0 and dropped below.
+                self.vec.reserve(bytes.len());
+                for &byte in bytes {
+                    let last = self.vec.len() - 1;
+                    self.vec[last] |= byte << shift;
+                    self.vec.push(byte >> (8 - shift));
+                }
+            }
+            self.nbits += nbits;
+            self.vec.truncate(bytes_in_bits(self.nbits));
+        }
+
+        /// Appends `count` copies of `value`, writing whole bytes where possible.
+        fn push_repeated(&mut self, count: usize, value: bool) {
+            let new_len = self.nbits + count;
+            while self.nbits % 8 != 0 && self.nbits < new_len {
+                self.push(value);
+            }
+            self.vec.resize(bytes_in_bits(new_len), byte_from_bool(value));
+            self.nbits = new_len;
+            self.set_unused_zero();
+        }
+
+        /// Sets the extra unused bits in the bitvector to 0. Safe methods maintain this invariant,
+        /// so this is only needed to restore it after writing through `as_mut_ptr` or constructing
+        /// with `from_raw_parts` from bytes with nonzero unused bits.
+        pub fn set_unused_zero(&mut self) {
+            if self.nbits % 8 == 0 { return }
+            let len = self.vec.len(); // avoid mutable borrow error
+            assert!(len > 0);
+
+            let byte = unsafe { self.vec.get_unchecked_mut(len - 1) };
+            // Pattern with all 1's in the used bits only, avoiding overflow check in debug.
+            let pattern = (Wrapping(1u8 << (self.nbits % 8)) - Wrapping(1u8)).0;
+            *byte &= pattern;
+        }
+    }
+}
+
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl BitVec {
+    impl_bitvec!(RawVec<u8>);
 
     ////////////////////////////////////////
     // Iterators
 
     /// Returns an iterator for the booleans in the bitvec.
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         self.into_iter()
     }
 }
 
-#[cfg(feature = "unstable")]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 impl<A: Allocator> BitVec<A> {
-    impl_bitvec!(Vec<u8, A>);
+    impl_bitvec!(RawVec<u8, A>);
 
     ////////////////////////////////////////
     // Iterators
 
     /// Returns an iterator for the booleans in the bitvec.
-    pub fn iter(&self) -> Iter<A> {
+    pub fn iter(&self) -> Iter<'_, A> {
         self.into_iter()
     }
 }
 
+/// Writes `bits` using `set` and `clear` for each bit, with a space between each group of `group`
+/// bits. A `group` of 0 disables grouping.
+fn write_bits<W: fmt::Write, I: Iterator<Item = bool>>(
+    w: &mut W, bits: I, group: usize, set: char, clear: char) -> fmt::Result
+{
+    for (index, val) in bits.enumerate() {
+        if group > 0 && index > 0 && index % group == 0 {
+            w.write_char(' ')?;
+        }
+        w.write_char(if val { set } else { clear })?;
+    }
+    Ok(())
+}
+
+/// Writes the `Debug` output of a bit vector. The alternate form `{:#?}` puts the bits on their own
+/// line below a ruler giving the index at the start of each group of 8 bits.
+fn write_debug<I: Iterator<Item = bool>>(f: &mut fmt::Formatter, nbits: usize, bits: I) -> fmt::Result {
+    if !f.alternate() || nbits == 0 {
+        write!(f, "BitVec{{{:?}: ", nbits)?;
+        write_bits(f, bits, 8, '1', '.')?;
+        return f.write_str("}");
+    }
+    write!(f, "BitVec{{{:?}:\n    ", nbits)?;
+    for start in (0..nbits).step_by(8) {
+        if start + 8 < nbits {
+            write!(f, "{:<9}", start)?;
+        } else {
+            write!(f, "{}", start)?;
+        }
+    }
+    f.write_str("\n    ")?;
+    write_bits(f, bits, 8, '1', '.')?;
+    f.write_str("\n}")
+}
+
 macro_rules! impl_display {
     () => {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            for (val, index) in self.iter().zip(0..usize::max_value()) {
-                if index > 0 && index % 8 == 0 {
-                    write!(f, " ")?;
-                }
-                write!(f, "{}", if val { "1" } else { "." })?;
-            }
-            Ok(())
+            $crate::write_bits(f, self.iter(), 8, '1', '.')
         }
     }
 }
 
-#[cfg(not(feature = "unstable"))]
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
 impl fmt::Debug for BitVec {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "BitVec{{{:?}: {}}}", self.nbits, &self)
+        write_debug(f, self.nbits, self.iter())
     }
 }
 
-#[cfg(not(feature = "unstable"))]
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
 impl fmt::Display for BitVec {
     impl_display!();
 }
 
-#[cfg(feature = "unstable")]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 impl<A: Allocator> fmt::Debug for BitVec<A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "BitVec{{{:?}: {}}}", self.nbits, &self)
+        write_debug(f, self.nbits, self.iter())
     }
 }
 
-#[cfg(feature = "unstable")]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 impl<A: Allocator> fmt::Display for BitVec<A> {
     impl_display!();
 }
 
+// `Borrow<[u8]>` is deliberately not implemented: bit vectors of different lengths can share the
+// same bytes, so `BitVec` equality cannot agree with `[u8]` equality as `Borrow` requires.
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl AsRef<[u8]> for BitVec {
+    fn as_ref(&self) -> &[u8] { &self.vec }
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<A: Allocator> AsRef<[u8]> for BitVec<A> {
+    fn as_ref(&self) -> &[u8] { &self.vec }
+}
+
+// Compares lengths and each bit, like `Vec<bool>` compared to a slice.
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl PartialEq<[bool]> for BitVec {
+    fn eq(&self, other: &[bool]) -> bool {
+        self.nbits == other.len() && self.iter().eq(other.iter().copied())
+    }
+}
+
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl PartialEq<&[bool]> for BitVec {
+    fn eq(&self, other: &&[bool]) -> bool { *self == **other }
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<A: Allocator> PartialEq<[bool]> for BitVec<A> {
+    fn eq(&self, other: &[bool]) -> bool {
+        self.nbits == other.len() && self.iter().eq(other.iter().copied())
+    }
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<A: Allocator> PartialEq<&[bool]> for BitVec<A> {
+    fn eq(&self, other: &&[bool]) -> bool { *self == **other }
+}
+
+// Like `Vec`, the following reserve only the lower bound of the iterator's size hint up front and
+// then grow amortized as needed, so an absent upper bound costs only O(log n) reallocations, and a
+// loose one, as from `filter`, does not overallocate.
 impl Extend<bool> for BitVec {
     fn extend<T>(&mut self, iterable: T)
         where T: IntoIterator<Item = bool>
     {
         let iter = iterable.into_iter();
-        let (min, max) = iter.size_hint();
-        self.reserve(max.unwrap_or(min));
+        let (min, _) = iter.size_hint();
+        self.reserve(min);
         for val in iter { self.push(val); }
     }
 }
@@ -399,19 +1831,40 @@ impl<'a> Extend<&'a bool> for BitVec {
         where T: IntoIterator<Item = &'a bool>
     {
         let iter = iterable.into_iter();
-        let (min, max) = iter.size_hint();
-        self.reserve(max.unwrap_or(min));
+        let (min, _) = iter.size_hint();
+        self.reserve(min);
         for val in iter { self.push(*val); }
     }
 }
 
+/// Appends each byte as eight bits in LSB 0 order.
+impl Extend<u8> for BitVec {
+    #[allow(clippy::manual_is_multiple_of)]
+    fn extend<T>(&mut self, iterable: T)
+        where T: IntoIterator<Item = u8>
+    {
+        let iter = iterable.into_iter();
+        if self.nbits % 8 == 0 {
+            // Byte-aligned, so the bytes can be appended as is.
+            self.vec.extend(iter);
+            self.nbits = self.vec.len() * 8;
+        } else {
+            let (min, _) = iter.size_hint();
+            self.vec.reserve(min);
+            for byte in iter {
+                for i in 0..8 { self.push(byte & (1u8 << i) != 0); }
+            }
+        }
+    }
+}
+
 impl core::iter::FromIterator<bool> for BitVec {
     fn from_iter<T>(iterable: T) -> Self
         where T: IntoIterator<Item = bool>
     {
         let iter = iterable.into_iter();
-        let (min, max) = iter.size_hint();
-        let mut vec = BitVec::with_capacity(max.unwrap_or(min));
+        let (min, _) = iter.size_hint();
+        let mut vec = BitVec::with_capacity(min);
         for val in iter { vec.push(val); }
         vec
     }
@@ -422,13 +1875,43 @@ impl<'a> core::iter::FromIterator<&'a bool> for BitVec {
         where T: IntoIterator<Item = &'a bool>
     {
         let iter = iterable.into_iter();
-        let (min, max) = iter.size_hint();
-        let mut vec = BitVec::with_capacity(max.unwrap_or(min));
+        let (min, _) = iter.size_hint();
+        let mut vec = BitVec::with_capacity(min);
         for &val in iter { vec.push(val); }
         vec
     }
 }
 
+/// Collects each byte as eight bits in LSB 0 order.
+impl core::iter::FromIterator<u8> for BitVec {
+    fn from_iter<T>(iterable: T) -> Self
+        where T: IntoIterator<Item = u8>
+    {
+        let vec: RawVec<u8> = iterable.into_iter().collect();
+        BitVec { nbits: vec.len() * 8, vec }
+    }
+}
+
+/// Concatenates the bit vectors in order.
+impl core::iter::FromIterator<BitVec> for BitVec {
+    fn from_iter<T>(iterable: T) -> Self
+        where T: IntoIterator<Item = BitVec>
+    {
+        let mut vec = BitVec::new();
+        vec.extend(iterable);
+        vec
+    }
+}
+
+/// Appends the bit vectors in order.
+impl Extend<BitVec> for BitVec {
+    fn extend<T>(&mut self, iterable: T)
+        where T: IntoIterator<Item = BitVec>
+    {
+        for mut other in iterable { self.append(&mut other); }
+    }
+}
+
 impl From<&[bool]> for BitVec {
     fn from(bools: &[bool]) -> Self {
         BitVec::from_bools(bools)
@@ -455,26 +1938,25 @@ macro_rules! impl_iter {
         type Item = bool;
 
         fn size_hint(&self) -> (usize, Option<usize>) {
-            let remaining = self.vec.len() - self.index;
+            let remaining = self.end - self.index;
             (remaining, Some(remaining))
         }
 
         fn count(self) -> usize {
-            self.vec.len() - self.index
+            self.end - self.index
         }
 
         fn last(self) -> Option<Self::Item> {
-            let len = self.vec.len();
-            if self.index < len {
-                Some(unsafe { self.vec.get_unchecked(len - 1) })
+            if self.index < self.end {
+                Some(unsafe { self.vec.get_unchecked(self.end - 1) })
             } else {
                 None
             }
         }
 
         fn nth(&mut self, count: usize) -> Option<Self::Item> {
-            self.index = if count >= self.vec.nbits - self.index {
-                self.vec.nbits
+            self.index = if count >= self.end - self.index {
+                self.end
             } else {
                 self.index + count
             };
@@ -482,7 +1964,7 @@ macro_rules! impl_iter {
         }
 
         fn next(&mut self) -> Option<Self::Item> {
-            if self.index >= self.vec.nbits {
+            if self.index >= self.end {
                 None
             } else {
                 let val = unsafe { self.vec.get_unchecked(self.index) };
@@ -493,9 +1975,38 @@ macro_rules! impl_iter {
     };
 }
 
+macro_rules! impl_double_ended_iter {
+    () => {
+        fn nth_back(&mut self, count: usize) -> Option<Self::Item> {
+            self.end = if count >= self.end - self.index {
+                self.index
+            } else {
+                self.end - count
+            };
+            self.next_back()
+        }
+
+        fn next_back(&mut self) -> Option<Self::Item> {
+            if self.index >= self.end {
+                None
+            } else {
+                self.end -= 1;
+                Some(unsafe { self.vec.get_unchecked(self.end) })
+            }
+        }
+    };
+}
+
+pub use self::builder::*;
+pub use self::error::*;
+pub use self::grid::*;
 pub use self::iter::*;
+pub use self::prefix::*;
+pub use self::set::*;
+pub use self::slice::*;
+pub use self::stream::*;
 
-#[cfg(not(feature = "unstable"))]
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
 mod iter {
     use super::BitVec;
 
@@ -505,23 +2016,42 @@ mod iter {
     {
         vec: &'a BitVec,
         index: usize,
+        end: usize,
     }
 
     /// Consumes and allows forward iteration through the bits of a bit vector.
+    #[derive(Clone)]
     pub struct IntoIter
     {
         vec: BitVec,
         index: usize,
+        end: usize,
     }
 
     impl<'a> Iterator for Iter<'a> {
         impl_iter!();
     }
 
+    impl<'a> DoubleEndedIterator for Iter<'a> {
+        impl_double_ended_iter!();
+    }
+
+    impl<'a> ExactSizeIterator for Iter<'a> {}
+
+    impl<'a> core::iter::FusedIterator for Iter<'a> {}
+
     impl Iterator for IntoIter {
         impl_iter!();
     }
 
+    impl DoubleEndedIterator for IntoIter {
+        impl_double_ended_iter!();
+    }
+
+    impl ExactSizeIterator for IntoIter {}
+
+    impl core::iter::FusedIterator for IntoIter {}
+
     impl<'a> IntoIterator for &'a BitVec {
         type Item = bool;
         type IntoIter = Iter<'a>;
@@ -529,6 +2059,7 @@ mod iter {
             Iter {
                 vec: self,
                 index: 0,
+                end: self.nbits,
             }
         }
     }
@@ -537,19 +2068,19 @@ mod iter {
         type Item = bool;
         type IntoIter = IntoIter;
         fn into_iter(self) -> Self::IntoIter {
+            let end = self.nbits;
             IntoIter {
                 vec: self,
                 index: 0,
+                end,
             }
         }
     }
 }
 
-#[cfg(feature = "unstable")]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 mod iter {
-    use alloc::alloc::Global;
-    use core::alloc::Allocator;
-    use super::BitVec;
+    use super::{Allocator, BitVec, Global};
 
     /// Allows forward iteration through the bits of a bit vector.
     #[derive(Clone)]
@@ -557,23 +2088,42 @@ mod iter {
     {
         vec: &'a BitVec<A>,
         index: usize,
+        end: usize,
     }
 
     /// Consumes and allows forward iteration through the bits of a bit vector.
+    #[derive(Clone)]
     pub struct IntoIter<A: Allocator = Global>
     {
         vec: BitVec<A>,
         index: usize,
+        end: usize,
     }
 
     impl<'a, A: Allocator> Iterator for Iter<'a, A> {
         impl_iter!();
     }
 
+    impl<'a, A: Allocator> DoubleEndedIterator for Iter<'a, A> {
+        impl_double_ended_iter!();
+    }
+
+    impl<'a, A: Allocator> ExactSizeIterator for Iter<'a, A> {}
+
+    impl<'a, A: Allocator> core::iter::FusedIterator for Iter<'a, A> {}
+
     impl<A: Allocator> Iterator for IntoIter<A> {
         impl_iter!();
     }
 
+    impl<A: Allocator> DoubleEndedIterator for IntoIter<A> {
+        impl_double_ended_iter!();
+    }
+
+    impl<A: Allocator> ExactSizeIterator for IntoIter<A> {}
+
+    impl<A: Allocator> core::iter::FusedIterator for IntoIter<A> {}
+
     impl<'a, A: Allocator> IntoIterator for &'a BitVec<A> {
         type Item = bool;
         type IntoIter = Iter<'a, A>;
@@ -581,6 +2131,7 @@ mod iter {
             Iter::<A> {
                 vec: self,
                 index: 0,
+                end: self.nbits,
             }
         }
     }
@@ -589,21 +2140,33 @@ mod iter {
         type Item = bool;
         type IntoIter = IntoIter<A>;
         fn into_iter(self) -> Self::IntoIter {
+            let end = self.nbits;
             IntoIter::<A> {
                 vec: self,
                 index: 0,
+                end,
             }
         }
     }
 }
 
+mod builder;
+#[cfg(feature = "serde")]
+mod de;
+mod error;
+mod grid;
+mod prefix;
+mod set;
+mod slice;
+mod stream;
+
 ////////////////////////////////////////////////////////////////////////////////
 // Indexing operations
 
 static TRUE: bool = true;
 static FALSE: bool = false;
 
-#[cfg(not(feature = "unstable"))]
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
 impl core::ops::Index<usize> for BitVec {
     type Output = bool;
 
@@ -614,7 +2177,7 @@ impl core::ops::Index<usize> for BitVec {
     }
 }
 
-#[cfg(feature = "unstable")]
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
 impl<A: Allocator> core::ops::Index<usize> for BitVec<A> {
     type Output = bool;
 
@@ -624,19 +2187,126 @@ impl<A: Allocator> core::ops::Index<usize> for BitVec<A> {
         if value { &TRUE } else { &FALSE }
     }
 }
+
 ////////////////////////////////////////////////////////////////////////////////
+// Bitwise operations
+//
+// The binary operators require equal lengths. With custom allocators, the right-hand side may use
+// a different allocator and the result uses the left-hand side's.
+
+macro_rules! impl_bitwise_op {
+    ($trait: ident, $method: ident, $assign_trait: ident, $assign_method: ident, $op: tt, $name: expr) => {
+        #[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+        impl core::ops::$assign_trait<&BitVec> for BitVec {
+            fn $assign_method(&mut self, rhs: &BitVec) {
+                assert!(self.len() == rhs.len(),
+                        concat!("Expected equal lengths to ", $name, ", got {} and {}."), self.len(), rhs.len());
+                for (a, &b) in self.vec.iter_mut().zip(rhs.vec.iter()) {
+                    *a = *a $op b;
+                }
+            }
+        }
 
-#[cfg(test)]
-mod test {
-    use super::BitVec;
-    use alloc::{vec::Vec, vec, format};
+        #[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+        impl core::ops::$trait<&BitVec> for &BitVec {
+            type Output = BitVec;
+            fn $method(self, rhs: &BitVec) -> BitVec {
+                let mut vec = self.clone();
+                core::ops::$assign_trait::$assign_method(&mut vec, rhs);
+                vec
+            }
+        }
 
-    #[test]
-    fn test_index() {
-        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
-        assert_eq!(vec[0], true);
-        assert_eq!(vec[4], false);
-        assert_eq!(vec[15], true);
+        #[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+        impl core::ops::$trait<BitVec> for BitVec {
+            type Output = BitVec;
+            fn $method(mut self, rhs: BitVec) -> BitVec {
+                core::ops::$assign_trait::$assign_method(&mut self, &rhs);
+                self
+            }
+        }
+
+        #[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+        impl<A: Allocator, B: Allocator> core::ops::$assign_trait<&BitVec<B>> for BitVec<A> {
+            fn $assign_method(&mut self, rhs: &BitVec<B>) {
+                assert!(self.len() == rhs.len(),
+                        concat!("Expected equal lengths to ", $name, ", got {} and {}."), self.len(), rhs.len());
+                for (a, &b) in self.vec.iter_mut().zip(rhs.vec.iter()) {
+                    *a = *a $op b;
+                }
+            }
+        }
+
+        #[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+        impl<A: Allocator + Clone, B: Allocator> core::ops::$trait<&BitVec<B>> for &BitVec<A> {
+            type Output = BitVec<A>;
+            fn $method(self, rhs: &BitVec<B>) -> BitVec<A> {
+                let mut vec = self.clone();
+                core::ops::$assign_trait::$assign_method(&mut vec, rhs);
+                vec
+            }
+        }
+
+        #[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+        impl<A: Allocator, B: Allocator> core::ops::$trait<BitVec<B>> for BitVec<A> {
+            type Output = BitVec<A>;
+            fn $method(mut self, rhs: BitVec<B>) -> BitVec<A> {
+                core::ops::$assign_trait::$assign_method(&mut self, &rhs);
+                self
+            }
+        }
+    };
+}
+
+impl_bitwise_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &, "and");
+impl_bitwise_op!(BitOr, bitor, BitOrAssign, bitor_assign, |, "or");
+impl_bitwise_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^, "xor");
+
+#[cfg(not(any(feature = "unstable", feature = "allocator-api2")))]
+impl core::ops::Not for BitVec {
+    type Output = BitVec;
+    fn not(mut self) -> BitVec {
+        for byte in self.vec.iter_mut() { *byte = !*byte; }
+        self.set_unused_zero();
+        self
+    }
+}
+
+#[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+impl<A: Allocator> core::ops::Not for BitVec<A> {
+    type Output = BitVec<A>;
+    fn not(mut self) -> BitVec<A> {
+        for byte in self.vec.iter_mut() { *byte = !*byte; }
+        self.set_unused_zero();
+        self
+    }
+}
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+// The tests deliberately exercise forms like `nth(0)` and `(&vec).iter()` that clippy would simplify.
+#[allow(clippy::bool_assert_comparison, clippy::iter_nth_zero, clippy::manual_repeat_n,
+        clippy::needless_borrow, clippy::needless_borrows_for_generic_args,
+        clippy::needless_range_loop)]
+mod test {
+    use super::{BitVec, bytes_in_bits};
+    use alloc::{vec::Vec, vec, format};
+
+    #[test]
+    fn test_index() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        assert_eq!(vec[0], true);
+        assert_eq!(vec[4], false);
+        assert_eq!(vec[15], true);
+    }
+
+    #[test]
+    fn test_bytes_in_bits() {
+        assert_eq!(bytes_in_bits(0), 0);
+        assert_eq!(bytes_in_bits(1), 1);
+        assert_eq!(bytes_in_bits(8), 1);
+        assert_eq!(bytes_in_bits(9), 2);
+        assert_eq!(bytes_in_bits(usize::MAX), usize::MAX / 8 + 1);
     }
 
     #[test]
@@ -745,6 +2415,22 @@ mod test {
         assert_eq!(vec.as_bytes(), &[0xff, 0xff, 0xff, 0x0f]);
     }
 
+    #[test]
+    fn test_with_bytes_mut_len() {
+        for len in [0, 1, 7, 8, 9, 63, 64, 65] {
+            let mut vec = BitVec::with_capacity(200);
+            vec.resize(len, true);
+            let (slice_len, padding) = vec.with_bytes_mut(|slice| {
+                let padding = slice.last().map(|byte| byte.leading_zeros());
+                slice.iter_mut().for_each(|byte| *byte = 0xff);
+                (slice.len(), padding)
+            });
+            assert_eq!(slice_len, bytes_in_bits(len));
+            assert_eq!(padding, if len == 0 { None } else { Some(((8 - len % 8) % 8) as u32) });
+            assert_eq!(vec.count_ones(), len);
+        }
+    }
+
     #[test]
     fn test_into_bytes() {
         let mut vec = BitVec::from_bytes(&[0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0xe3]);
@@ -754,6 +2440,15 @@ mod test {
         assert_eq!(vec, &[0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23]);
     }
 
+    #[test]
+    fn test_bool_vec() {
+        let bools = [true, false, false, true, true, true, false, true, false, true, true];
+        let vec = BitVec::from_bools(&bools);
+        assert_eq!(vec.to_bool_vec(), bools);
+        assert_eq!(vec.into_bool_vec(), bools);
+        assert_eq!(BitVec::new().into_bool_vec(), Vec::<bool>::new());
+    }
+
     #[test]
     fn test_get_set_index() {
         let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
@@ -827,6 +2522,26 @@ mod test {
         assert_eq!(vec.as_bytes(), &[0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0b01010101]);
     }
 
+    #[test]
+    fn test_push_pop_byte() {
+        let mut vec = BitVec::new();
+        vec.push_byte(0xef);
+        vec.push_byte(0xa5);
+        assert_eq!(vec.len(), 16);
+        assert_eq!(vec.as_bytes(), &[0xef, 0xa5]);
+        assert_eq!(vec.pop_byte(), Some(0xa5));
+        assert_eq!(vec.pop_byte(), Some(0xef));
+        assert_eq!(vec.pop_byte(), None);
+        assert_eq!(vec.len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "byte-aligned")]
+    fn test_push_byte_validation() {
+        let mut vec = BitVec::from_elem(3, true);
+        vec.push_byte(0xff);
+    }
+
     #[test]
     fn test_clear() {
         let mut vec = BitVec::from_bytes(&[0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0xe3]);
@@ -908,6 +2623,19 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_debug_alternate() {
+        assert_eq!(
+            format!("{:#?}", &BitVec::from_bytes(&[0xef, 0xa5, 0x71])),
+            "BitVec{24:\n    0        8        16\n    1111.111 1.1..1.1 1...111.\n}"
+        );
+        assert_eq!(
+            format!("{:#?}", &BitVec::from_bytes_with_len(&[0xef, 0xa5], 11)),
+            "BitVec{11:\n    0        8\n    1111.111 1.1\n}"
+        );
+        assert_eq!(format!("{:#?}", &BitVec::new()), "BitVec{0: }");
+    }
+
     #[test]
     fn test_swap() {
         let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
@@ -919,6 +2647,24 @@ mod test {
         assert_eq!(vec.as_bytes(), &[0xcf, 0xa5, 0xf1]);
     }
 
+    #[test]
+    fn test_swap_unchecked() {
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        unsafe { vec.swap_unchecked(0, 23) };
+        assert_eq!(vec.as_bytes(), &[0xee, 0xa5, 0xf1]);
+        unsafe { vec.swap_unchecked(0, 5) };
+        assert_eq!(vec.as_bytes(), &[0xcf, 0xa5, 0xf1]);
+        unsafe { vec.swap_unchecked(9, 9) };
+        assert_eq!(vec.as_bytes(), &[0xcf, 0xa5, 0xf1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 24 out of bounds [0, 24)")]
+    fn test_swap_out_of_bounds() {
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        vec.swap(0, 24);
+    }
+
     #[test]
     fn test_capacity_reserve() {
         let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
@@ -929,6 +2675,22 @@ mod test {
         assert!(vec.capacity() >= new_capacity);
     }
 
+    #[test]
+    fn test_resize_and_shrink() {
+        let mut vec = BitVec::from_elem(1000, true);
+        vec.resize(20, false);
+        assert!(vec.capacity() >= 1000);
+        vec.resize_and_shrink(20, false);
+        assert_eq!(vec.len(), 20);
+        assert!(vec.capacity() >= 20 && vec.capacity() <= bytes_in_bits(20) * 8);
+        assert_eq!(vec.count_ones(), 20);
+
+        vec.resize_and_shrink(30, false);
+        assert_eq!(vec.len(), 30);
+        assert_eq!(vec.count_ones(), 20);
+        assert!(vec.capacity() >= 30);
+    }
+
     #[test]
     fn test_truncate_extend() {
         let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
@@ -950,6 +2712,22 @@ mod test {
         assert_eq!(vec.as_bytes(), &[0xef, 0xf5, 0x7f]);
     }
 
+    #[test]
+    fn test_bytes_collect_extend() {
+        let vec: BitVec = [0xab, 0xcd].into_iter().collect();
+        assert_eq!(vec, BitVec::from_bytes(&[0xab, 0xcd]));
+
+        let mut vec = BitVec::from_bytes(&[0xef]);
+        vec.extend([0xa5u8]);
+        assert_eq!(vec, BitVec::from_bytes(&[0xef, 0xa5]));
+
+        // Unaligned extension shifts the bytes across byte boundaries.
+        vec.truncate(12);
+        vec.extend([0xa5u8, 0x71]);
+        assert_eq!(vec.len(), 28);
+        assert_eq!(vec.as_bytes(), &[0xef, 0x55, 0x1a, 0x07]);
+    }
+
     #[test]
     fn test_resize() {
         let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
@@ -967,6 +2745,54 @@ mod test {
         assert_eq!(vec.as_bytes(), &[0xef, 0xf5, 0x01]);
     }
 
+    #[test]
+    fn test_into_iter_clone() {
+        let mut iter = BitVec::from_bytes(&[0xef, 0xa5]).into_iter();
+        assert_eq!(iter.nth(3), Some(true));
+        let mut clone = iter.clone();
+        assert_eq!(clone.next(), Some(false));
+        assert_eq!(clone.next(), Some(true));
+        assert_eq!(clone.size_hint(), (10, Some(10)));
+        assert_eq!(iter.size_hint(), (12, Some(12)));
+        assert_eq!(iter.next(), Some(false));
+    }
+
+    #[test]
+    fn test_iter_double_ended() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        let bools: Vec<bool> = vec.iter().collect();
+        let reversed: Vec<bool> = vec.iter().rev().collect();
+        assert_eq!(reversed, bools.iter().rev().cloned().collect::<Vec<bool>>());
+        let reversed: Vec<bool> = vec.clone().into_iter().rev().collect();
+        assert_eq!(reversed, bools.iter().rev().cloned().collect::<Vec<bool>>());
+
+        assert_eq!(vec.iter().nth_back(3), Some(bools[20]));
+        assert_eq!(vec.iter().nth_back(23), Some(bools[0]));
+        assert_eq!(vec.iter().nth_back(24), None);
+
+        // Cursors from both ends meet without crossing.
+        let mut iter = vec.iter();
+        assert_eq!(iter.nth(10), Some(bools[10]));
+        assert_eq!(iter.nth_back(3), Some(bools[20]));
+        assert_eq!(iter.size_hint(), (9, Some(9)));
+        assert_eq!(iter.nth_back(8), Some(bools[11]));
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn test_resize_with() {
+        let mut vec = BitVec::from_elem(4, false);
+        let mut counter = 0;
+        vec.resize_with(10, || { counter += 1; counter % 2 == 1 });
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec.as_bytes(), &[0b01010000, 0b01]);
+
+        vec.resize_with(3, || true);
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.as_bytes(), &[0]);
+    }
+
     #[test]
     fn test_iter_overrides() {
         let from: &[bool] = &[true, false, false, true, true, false, false, true, true, true, false];
@@ -996,6 +2822,1850 @@ mod test {
         assert_eq!(iter.nth(0), None);
     }
 
+    #[test]
+    fn test_parity() {
+        let even = BitVec::from_bytes(&[0b00110000, 0b10000001]);
+        assert_eq!(even.count_ones(), 4);
+        assert!(!even.parity());
+        let odd = BitVec::from_bytes(&[0b00110000, 0b10100001]);
+        assert_eq!(odd.count_ones(), 5);
+        assert!(odd.parity());
+        assert!(!BitVec::new().parity());
+
+        assert!(odd.parity_in(0..5));
+        assert!(!odd.parity_in(4..6));
+        assert!(odd.parity_in(4..16));
+        assert!(!odd.parity_in(4..14));
+        assert!(!odd.parity_in(8..8));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_parity_in_validation() {
+        let _ = BitVec::from_bytes(&[0xef]).parity_in(4..9);
+    }
+
+    #[test]
+    fn test_nth_one() {
+        let vec = BitVec::from_bytes(&[0b00010010, 0b00000001, 0b10100000]);
+        assert_eq!(vec.nth_one(0), Some(1));
+        assert_eq!(vec.nth_one(1), Some(4));
+        assert_eq!(vec.nth_one(2), Some(8));
+        assert_eq!(vec.nth_one(3), Some(21));
+        assert_eq!(vec.nth_one(4), Some(23));
+        assert_eq!(vec.nth_one(5), None);
+        assert_eq!(BitVec::new().nth_one(0), None);
+    }
+
+    #[test]
+    fn test_split_at() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        for mid in [0, 5, 8, 13, 24] {
+            let (left, right) = vec.split_at(mid);
+            assert_eq!(left.len(), mid);
+            assert_eq!(right.len(), 24 - mid);
+            let bits: Vec<bool> = left.iter().chain(right.iter()).collect();
+            assert_eq!(bits, vec.iter().collect::<Vec<bool>>());
+        }
+
+        let (left, right) = vec.split_at(13);
+        assert_eq!(format!("{:?}", left), "BitSlice{13: 1111.111 1.1..}");
+        assert_eq!(format!("{:?}", right), "BitSlice{11: 1.11...1 11.}");
+        assert_eq!(right.get(0), Some(true));
+        assert_eq!(right.get(11), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_split_at_validation() {
+        let _ = BitVec::from_bytes(&[0xef, 0xa5, 0x71]).split_at(25);
+    }
+
+    #[test]
+    fn test_interleave() {
+        let a = BitVec::from_bools(&[true, true, false, true, false, false, true, false, true, true, true, false]);
+        let b = BitVec::from_bools(&[false, true, true, true, false, true, false, false, true, false, false, false]);
+        let vec = a.interleave(&b);
+        assert_eq!(vec.len(), 24);
+        assert_eq!(vec.as_bytes(), &[0b11101101, 0b00011000, 0b00010111]);
+        assert_eq!(vec.deinterleave(), (a, b));
+        assert_eq!(BitVec::new().interleave(&BitVec::new()), BitVec::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "equal lengths")]
+    fn test_interleave_validation() {
+        let _ = BitVec::from_elem(12, true).interleave(&BitVec::from_elem(11, true));
+    }
+
+    #[test]
+    fn test_transpose8() {
+        let vec = BitVec::from_bytes(&[0xff, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(vec.transpose8().as_bytes(), &[1, 1, 1, 1, 1, 1, 1, 1]);
+
+        let vec = BitVec::from_bytes(&[0x01, 0x03, 0x07, 0x0f, 0x1f, 0x3f, 0x7f, 0xff]);
+        assert_eq!(vec.transpose8().as_bytes(), &[0xff, 0xfe, 0xfc, 0xf8, 0xf0, 0xe0, 0xc0, 0x80]);
+        assert_eq!(vec.transpose8().transpose8(), vec);
+
+        // Bytes not a multiple of 8 exercise both the blocks and the tail, with unaligned planes.
+        for n in [0, 3, 8, 11, 16, 21] {
+            let bytes = (0..n as u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect::<Vec<_>>();
+            let vec = BitVec::from_bytes(&bytes);
+            let transposed = vec.transpose8();
+            assert_eq!(transposed.len(), 8 * n);
+            for i in 0..n {
+                for j in 0..8 {
+                    assert_eq!(transposed[j * n + i], vec[8 * i + j]);
+                }
+            }
+        }
+
+        // Planes of 3 bits: 111 100 110 100 001 111 101 110.
+        let transposed = BitVec::from_bytes(&[0xef, 0xa5, 0x71]).transpose8();
+        assert_eq!(format!("{}", transposed), "1111..11 .1....11 111.111.");
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a multiple of 8 bits to transpose, got 12.")]
+    fn test_transpose8_validation() {
+        let _ = BitVec::from_elem(12, true).transpose8();
+    }
+
+    #[test]
+    fn test_runs() {
+        let vec = BitVec::from_bytes(&[0xf0]);
+        assert_eq!(vec.runs().collect::<Vec<_>>(), vec![(false, 4), (true, 4)]);
+        assert_eq!(BitVec::new().runs().next(), None);
+
+        // Runs span bytes and stop at the length rather than the zeroed padding.
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        vec.truncate(22);
+        assert_eq!(vec.runs().collect::<Vec<_>>(),
+                   vec![(true, 4), (false, 1), (true, 4), (false, 1), (true, 1), (false, 2),
+                        (true, 1), (false, 1), (true, 2), (false, 3), (true, 2)]);
+    }
+
+    #[test]
+    fn test_rle() {
+        let runs = vec![(true, 3), (false, 9), (true, 1), (false, 2), (true, 12), (false, 4)];
+        let vec = BitVec::from_rle(&runs);
+        assert_eq!(vec.len(), 31);
+        assert_eq!(vec.as_bytes(), &[0x07, 0x90, 0xff, 0x07]);
+        assert_eq!(vec.to_rle(), runs);
+
+        assert_eq!(BitVec::from_rle(&[]), BitVec::new());
+        assert_eq!(BitVec::new().to_rle(), vec![]);
+    }
+
+    #[test]
+    fn test_gray() {
+        assert_eq!(BitVec::from_bytes(&[0b0110]).to_gray().as_bytes(), &[0b0101]);
+        assert_eq!(BitVec::from_bytes(&[0x00, 0x01]).to_gray().as_bytes(), &[0x80, 0x01]);
+
+        for &len in &[0, 1, 7, 8, 13, 24] {
+            let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+            vec.truncate(len);
+            let gray = vec.to_gray();
+            assert_eq!(gray.len(), len);
+            assert_eq!(gray.from_gray(), vec);
+        }
+
+        // Consecutive integers have Gray codes that differ in exactly one bit.
+        for value in 0u16..1023 {
+            let mut vec = BitVec::from_bytes(&value.to_le_bytes());
+            vec.truncate(10);
+            let mut next = BitVec::from_bytes(&(value + 1).to_le_bytes());
+            next.truncate(10);
+            let (gray, next_gray) = (vec.to_gray(), next.to_gray());
+            assert_eq!(gray.iter().zip(next_gray.iter()).filter(|(a, b)| a != b).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_increment() {
+        let mut vec = BitVec::from_elem(4, true);
+        assert!(vec.increment());
+        assert_eq!(vec.as_bytes(), &[0]);
+        assert_eq!(vec.len(), 4);
+
+        let mut vec = BitVec::from_bools(&[true, false, false, false]);
+        assert!(!vec.increment());
+        assert_eq!(vec.as_bytes(), &[0b0010]);
+
+        // Carries propagate across bytes.
+        let mut vec = BitVec::from_bytes(&[0xff, 0x05]);
+        vec.truncate(12);
+        assert!(!vec.increment());
+        assert_eq!(vec.as_bytes(), &[0x00, 0x06]);
+        vec.with_bytes_mut(|bytes| bytes.copy_from_slice(&[0xff, 0x0f]));
+        assert!(vec.increment());
+        assert_eq!(vec.as_bytes(), &[0x00, 0x00]);
+
+        let mut vec = BitVec::from_elem(16, true);
+        assert!(vec.increment());
+        assert_eq!(vec.as_bytes(), &[0, 0]);
+    }
+
+    #[test]
+    fn test_wrapping_add() {
+        let a = BitVec::from_bools(&[true, false, true, false]);
+        let b = BitVec::from_bools(&[true, true, false, false]);
+        assert_eq!(a.wrapping_add(&b), BitVec::from_bools(&[false, false, false, true]));
+
+        // Carry out of the top bit wraps around.
+        let a = BitVec::from_elem(12, true);
+        let mut b = BitVec::from_bytes(&[0x04, 0x00]);
+        b.truncate(12);
+        assert_eq!(a.wrapping_add(&b).as_bytes(), &[0x03, 0x00]);
+
+        // Carries propagate across bytes.
+        let a = BitVec::from_bytes(&[0xff, 0x01, 0x80]);
+        let b = BitVec::from_bytes(&[0x01, 0x00, 0x80]);
+        assert_eq!(a.wrapping_add(&b).as_bytes(), &[0x00, 0x02, 0x00]);
+    }
+
+    #[test]
+    #[should_panic(expected = "equal lengths")]
+    fn test_wrapping_add_validation() {
+        let _ = BitVec::from_elem(4, true).wrapping_add(&BitVec::from_elem(5, true));
+    }
+
+    #[test]
+    fn test_as_ref() {
+        fn byte_sum<T: AsRef<[u8]>>(bytes: T) -> u32 {
+            bytes.as_ref().iter().map(|&b| b as u32).sum()
+        }
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        assert_eq!(byte_sum(&vec), 0xef + 0xa5 + 0x71);
+        vec.truncate(20);
+        assert_eq!(vec.as_ref(), vec.as_bytes());
+        assert_eq!(byte_sum(&vec), 0xef + 0xa5 + 0x01);
+    }
+
+    #[test]
+    fn test_from_bytes_with_len() {
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5], 11);
+        assert_eq!(vec.len(), 11);
+        assert_eq!(vec.as_bytes(), &[0xef, 0x05]);
+
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 8);
+        assert_eq!(vec, BitVec::from_bytes(&[0xef]));
+        assert_eq!(BitVec::from_bytes_with_len(&[0xef], 0), BitVec::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected #bits 17 <= 8 x (#bytes 2 in slice)")]
+    fn test_from_bytes_with_len_validation() {
+        let _ = BitVec::from_bytes_with_len(&[0xef, 0xa5], 17);
+    }
+
+    #[test]
+    fn test_raw_parts() {
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        vec.truncate(20);
+        let (bytes, nbits) = vec.clone().into_raw_parts();
+        assert_eq!(bytes, &[0xef, 0xa5, 0x01]);
+        assert_eq!(nbits, 20);
+        let rebuilt = unsafe { BitVec::from_raw_parts(bytes, nbits) };
+        assert_eq!(rebuilt, vec);
+    }
+
+    #[test]
+    fn test_ptr() {
+        let mut vec = BitVec::from_elem(12, false);
+        assert_eq!(vec.as_ptr(), vec.as_bytes().as_ptr());
+        unsafe {
+            *vec.as_mut_ptr() = 0xef;
+            *vec.as_mut_ptr().add(1) = 0xa5;
+        }
+        vec.set_unused_zero();
+        assert_eq!(vec.as_bytes(), &[0xef, 0x05]);
+    }
+
+    #[test]
+    fn test_format_bits() {
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5]);
+        vec.truncate(12);
+        assert_eq!(vec.format_bits(4, '1', '0'), "1111 0111 1010");
+        assert_eq!(vec.format_bits(0, '#', '_'), "####_####_#_");
+        assert_eq!(vec.format_bits(8, '1', '.'), format!("{}", vec));
+        assert_eq!(BitVec::new().format_bits(4, '1', '0'), "");
+    }
+
+    #[test]
+    fn test_array_chunks() {
+        let bytes: Vec<u8> = (0..12).collect();
+        let vec = BitVec::from_bytes(&bytes);
+        let chunks: Vec<[u8; 4]> = vec.array_chunks::<4>().collect();
+        assert_eq!(chunks, vec![[0, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, 11]]);
+        assert_eq!(vec.array_chunks_remainder::<4>(), &[]);
+
+        let chunks: Vec<[u8; 5]> = vec.array_chunks::<5>().collect();
+        assert_eq!(chunks, vec![[0, 1, 2, 3, 4], [5, 6, 7, 8, 9]]);
+        assert_eq!(vec.array_chunks_remainder::<5>(), &[10, 11]);
+    }
+
+    #[test]
+    fn test_copy_bits_from() {
+        let src = BitVec::from_bytes(&[0xef, 0xa5]);
+        let mut vec = BitVec::from_elem(20, false);
+        vec.copy_bits_from(5, &src, 3..11);
+        // src[3..11] is 1.1111.1, which lands at indices [5, 13).
+        assert_eq!(vec.as_bytes(), &[0b10100000, 0b00010111, 0x00]);
+
+        let mut vec = BitVec::from_elem(20, true);
+        vec.copy_bits_from(5, &src, 3..11);
+        assert_eq!(vec.as_bytes(), &[0b10111111, 0b11110111, 0x0f]);
+
+        vec.copy_bits_from(20, &src, 16..16);
+        assert_eq!(vec.len(), 20);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_copy_bits_from_validation() {
+        let src = BitVec::from_bytes(&[0xef, 0xa5]);
+        BitVec::from_elem(20, false).copy_bits_from(13, &src, 3..11);
+    }
+
+    #[test]
+    fn test_jaccard() {
+        let a = BitVec::from_bytes(&[0b00001111, 0b1]);
+        let b = BitVec::from_bytes(&[0b00111100, 0b0]);
+        // Intersection {2, 3}, union {0, 1, 2, 3, 4, 5, 8}.
+        assert_eq!(a.jaccard(&b), 2.0 / 7.0);
+        assert_eq!(a.jaccard(&a), 1.0);
+        assert_eq!(a.jaccard(&BitVec::from_elem(16, false)), 0.0);
+        assert_eq!(BitVec::from_elem(9, false).jaccard(&BitVec::from_elem(9, false)), 1.0);
+        assert_eq!(BitVec::new().jaccard(&BitVec::new()), 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "equal lengths")]
+    fn test_jaccard_validation() {
+        let _ = BitVec::from_elem(4, true).jaccard(&BitVec::from_elem(5, true));
+    }
+
+    #[test]
+    fn test_majority3() {
+        let a = BitVec::from_bytes(&[0xef, 0xa5]);
+        let mut b = a.clone();
+        b.set(3, false);
+        let mut c = a.clone();
+        c.set(12, true);
+        assert_eq!(BitVec::majority3(&a, &b, &c), a);
+        assert_eq!(BitVec::majority3(&a, &b, &b), b);
+    }
+
+    #[test]
+    #[should_panic(expected = "equal lengths")]
+    fn test_majority3_validation() {
+        let a = BitVec::from_elem(4, true);
+        let _ = BitVec::majority3(&a, &a, &BitVec::from_elem(5, true));
+    }
+
+    #[test]
+    fn test_invariants() {
+        let mut vec = BitVec::from_elem(12, true);
+        vec.debug_assert_invariants();
+        unsafe { *vec.as_mut_ptr().add(1) = 0xff; }
+        vec.set_unused_zero();
+        vec.debug_assert_invariants();
+        assert_eq!(vec.as_bytes(), &[0xff, 0x0f]);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "unused bits")]
+    fn test_invariants_dirty() {
+        let mut vec = BitVec::from_elem(12, true);
+        unsafe { *vec.as_mut_ptr().add(1) = 0xff; }
+        vec.debug_assert_invariants();
+    }
+
+    #[test]
+    fn test_bit_reader() {
+        // Pack 0b101 (3 bits), 0x3c (7 bits), 1 (1 bit), and 0xbeef (16 bits).
+        let mut vec = BitVec::new();
+        for &(value, width) in &[(0b101u64, 3), (0x3c, 7), (1, 1), (0xbeef, 16)] {
+            for i in 0..width { vec.push(value >> i & 1 == 1); }
+        }
+
+        let mut reader = vec.bit_reader();
+        assert_eq!(reader.remaining(), 27);
+        assert_eq!(reader.read_bits(3), Some(0b101));
+        assert_eq!(reader.read_bits(7), Some(0x3c));
+        assert_eq!(reader.read_bit(), Some(true));
+        assert_eq!(reader.position(), 11);
+        assert_eq!(reader.read_bits(17), None);
+        assert_eq!(reader.read_bits(16), Some(0xbeef));
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(reader.read_bit(), None);
+        assert_eq!(reader.read_bits(0), Some(0));
+
+        let vec = BitVec::from_bytes(&0x0123_4567_89ab_cdefu64.to_le_bytes());
+        assert_eq!(vec.bit_reader().read_bits(64), Some(0x0123_4567_89ab_cdef));
+    }
+
+    #[test]
+    fn test_bit_writer() {
+        let mut vec = BitVec::new();
+        let mut writer = vec.bit_writer();
+        writer.write_bit(true).write_bits(0x55, 7).write_bits(0xabcde, 20);
+        assert_eq!(writer.position(), 28);
+        writer.align(false);
+        assert_eq!(writer.position(), 32);
+        assert_eq!(vec.len(), 32);
+
+        let mut reader = vec.bit_reader();
+        assert_eq!(reader.read_bits(1), Some(1));
+        assert_eq!(reader.read_bits(7), Some(0x55));
+        assert_eq!(reader.read_bits(20), Some(0xabcde));
+        assert_eq!(reader.read_bits(4), Some(0));
+        assert_eq!(reader.remaining(), 0);
+
+        let mut vec = BitVec::new();
+        vec.push_bits(u64::MAX, 64);
+        vec.bit_writer().write_bit(true).align(true);
+        assert_eq!(vec.as_bytes(), &[0xff; 9]);
+    }
+
+    #[test]
+    fn test_field_reader() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        let mut reader = vec.field_reader();
+        assert_eq!(reader.take(4), Some(BitVec::from_u64(0xf, 4)));
+        assert_eq!(reader.take(12), Some(BitVec::from_u64(0xa5e, 12)));
+        assert_eq!(reader.position(), 16);
+        assert_eq!(reader.take(9), None);
+        assert_eq!(reader.take(8), Some(BitVec::from_bytes(&[0x71])));
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(reader.take(0), Some(BitVec::new()));
+        assert_eq!(reader.take(1), None);
+    }
+
+    #[test]
+    fn test_set_positions_delta() {
+        let mut vec = BitVec::from_elem(1000, false);
+        for &index in &[3, 4, 64, 500, 999] {
+            vec.set(index, true);
+        }
+        let deltas = vec.set_positions_delta();
+        assert_eq!(deltas, vec![3, 1, 60, 436, 499]);
+        assert_eq!(BitVec::from_set_positions_delta(1000, &deltas), vec);
+
+        assert_eq!(BitVec::from_elem(10, false).set_positions_delta(), vec![]);
+        assert_eq!(BitVec::from_set_positions_delta(10, &[]), BitVec::from_elem(10, false));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_from_set_positions_delta_validation() {
+        let _ = BitVec::from_set_positions_delta(10, &[3, 7]);
+    }
+
+    #[test]
+    fn test_stuff_bits() {
+        let vec = BitVec::from_bools(&[false, true, true, true, true, true, true, false, true]);
+        let stuffed = vec.stuff_bits(5, true);
+        assert_eq!(stuffed, BitVec::from_bools(
+            &[false, true, true, true, true, true, false, true, false, true]));
+        assert_eq!(stuffed.destuff_bits(5, true), vec);
+
+        let vec = BitVec::from_elem(12, true);
+        let stuffed = vec.stuff_bits(5, true);
+        assert_eq!(stuffed.len(), 14);
+        assert_eq!(stuffed.destuff_bits(5, true), vec);
+
+        let vec = BitVec::from_bytes(&[0x00, 0xef, 0x00]);
+        assert_eq!(vec.stuff_bits(3, false).destuff_bits(3, false), vec);
+    }
+
+    #[test]
+    fn test_elias_gamma() {
+        let values = [1, 2, 7, 42, 1000];
+        let vec = BitVec::encode_elias_gamma(&values);
+        // Codes of lengths 1, 3, 5, 11 and 19.
+        assert_eq!(vec.len(), 39);
+        assert_eq!(format!("{}", BitVec::encode_elias_gamma(&[1, 2, 7])), "1.1...11 1");
+        assert_eq!(vec.decode_elias_gamma(), values);
+        assert_eq!(BitVec::new().decode_elias_gamma(), Vec::<u32>::new());
+        assert_eq!(BitVec::encode_elias_gamma(&[u32::MAX]).decode_elias_gamma(), [u32::MAX]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a complete Elias gamma codeword at bit 4.")]
+    fn test_elias_gamma_truncated() {
+        let mut vec = BitVec::encode_elias_gamma(&[1, 2, 42]);
+        vec.pop();
+        vec.decode_elias_gamma();
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected Elias gamma values >= 1, got 0.")]
+    fn test_elias_gamma_zero() {
+        BitVec::encode_elias_gamma(&[3, 0]);
+    }
+
+    #[test]
+    fn test_u64() {
+        assert_eq!(BitVec::from_u64(0b1011, 4).to_u64(), Some(0b1011));
+        assert_eq!(BitVec::from_u64(0xff0f, 4), BitVec::from_bools(&[true; 4]));
+        assert_eq!(BitVec::from_u64(0, 0).to_u64(), Some(0));
+        assert_eq!(BitVec::from_u64(u64::MAX, 64).to_u64(), Some(u64::MAX));
+        assert_eq!(BitVec::from_u64(0x8000_0000_0000_0001, 64).to_u64(), Some(0x8000_0000_0000_0001));
+        assert_eq!(BitVec::from_elem(70, false).to_u64(), None);
+    }
+
+    #[test]
+    fn test_grow() {
+        let mut vec = BitVec::from_bools(&[true, false, true]);
+        vec.grow(14, true);
+        assert_eq!(vec.len(), 17);
+        assert_eq!(vec.as_bytes(), &[0xfd, 0xff, 0x01]);
+        vec.grow(10, false);
+        assert_eq!(vec.len(), 27);
+        assert_eq!(vec.as_bytes(), &[0xfd, 0xff, 0x01, 0x00]);
+        vec.grow(0, true);
+        assert_eq!(vec.len(), 27);
+
+        let mut resized = BitVec::from_bools(&[true, false, true]);
+        resized.resize(17, true);
+        resized.resize(27, false);
+        assert_eq!(vec, resized);
+    }
+
+    #[test]
+    fn test_splice() {
+        let mut vec = BitVec::from_bytes(&[0x0f, 0xa5]);
+        let replacement = BitVec::from_bools(
+            &[true, false, true, true, false, false, true, true, true, false]);
+        vec.splice(4..8, &replacement);
+        assert_eq!(vec.len(), 22);
+        assert_eq!(format!("{}", vec), "11111.11 ..111.1. 1..1.1");
+        vec.splice(4..14, &BitVec::new());
+        assert_eq!(vec.len(), 12);
+        assert_eq!(format!("{}", vec), "11111.1. .1.1");
+        vec.splice(12..12, &BitVec::from_elem(3, true));
+        assert_eq!(vec.len(), 15);
+        assert_eq!(format!("{}", vec), "11111.1. .1.1111");
+    }
+
+    #[test]
+    #[should_panic(expected = "Range [4, 9) out of bounds [0, 8)")]
+    fn test_splice_out_of_bounds() {
+        let mut vec = BitVec::from_bytes(&[0x0f]);
+        vec.splice(4..9, &BitVec::new());
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut vec = BitVec::from_bools(&[true, true, true, false, false, false, true, true, false]);
+        vec.dedup();
+        assert_eq!(vec, BitVec::from_bools(&[true, false, true, false]));
+        vec.dedup();
+        assert_eq!(vec.len(), 4);
+
+        let mut vec = BitVec::from_bytes(&[0xff, 0xff, 0x01]);
+        vec.dedup();
+        assert_eq!(vec, BitVec::from_bools(&[true, false]));
+        vec.debug_assert_invariants();
+
+        let mut vec = BitVec::new();
+        vec.dedup();
+        assert!(vec.is_empty());
+        let mut vec = BitVec::from_bools(&[true]);
+        vec.dedup();
+        assert_eq!(vec, BitVec::from_bools(&[true]));
+    }
+
+    #[test]
+    fn test_common_prefix_len() {
+        let a = BitVec::from_bools(&[true, false, true, true]);
+        let b = BitVec::from_bools(&[true, false, true, false, false, true]);
+        assert_eq!(a.common_prefix_len(&b), 3);
+        assert_eq!(b.common_prefix_len(&a), 3);
+
+        // Strict prefixes, including across the zeroed padding bits.
+        let c = BitVec::from_bools(&[true, false, true]);
+        assert_eq!(c.common_prefix_len(&a), 3);
+        assert_eq!(c.common_prefix_len(&b), 3);
+        let d = BitVec::from_bools(&[true, false, true, false]);
+        assert_eq!(d.common_prefix_len(&b), 4);
+
+        let e = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        let f = BitVec::from_bytes(&[0xef, 0xa5, 0x79]);
+        assert_eq!(e.common_prefix_len(&f), 19);
+        assert_eq!(e.common_prefix_len(&e), 24);
+        assert_eq!(e.common_prefix_len(&BitVec::new()), 0);
+    }
+
+    #[test]
+    fn test_try_get() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        assert_eq!(vec.try_get(0), Ok(true));
+        assert_eq!(vec.try_get(4), Ok(false));
+        assert_eq!(vec.try_get(23), Ok(false));
+        let err = vec.try_get(24).unwrap_err();
+        assert_eq!(err, super::OutOfBounds { index: 24, len: 24 });
+        assert_eq!(format!("{}", err), "Index 24 out of bounds [0, 24)");
+        #[cfg(feature = "std")]
+        {
+            let err: &dyn std::error::Error = &err;
+            assert!(err.source().is_none());
+        }
+    }
+
+    #[test]
+    fn test_reversed() {
+        let mut vec = BitVec::from_bytes(&[0x01]);
+        vec.truncate(3);
+        let reversed = vec.reversed();
+        assert_eq!(reversed, BitVec::from_bools(&[false, false, true]));
+        assert_eq!(reversed.as_bytes(), &[0x04]);
+        assert_eq!(reversed.reversed(), vec);
+
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 21);
+        assert_eq!(vec.reversed().len(), 21);
+        assert_eq!(vec.reversed().reversed(), vec);
+        assert_eq!(BitVec::from_bytes(&[0xef, 0xa5]).reversed().as_bytes(), &[0xa5, 0xf7]);
+        assert_eq!(BitVec::new().reversed(), BitVec::new());
+    }
+
+    #[test]
+    fn test_count_transitions() {
+        let vec = BitVec::from_bools(&[true, false, true, false, true, false]);
+        assert_eq!(vec.count_transitions(), 5);
+        assert_eq!(BitVec::from_elem(21, true).count_transitions(), 0);
+        assert_eq!(BitVec::from_elem(21, false).count_transitions(), 0);
+        assert_eq!(BitVec::new().count_transitions(), 0);
+        assert_eq!(BitVec::from_bools(&[true]).count_transitions(), 0);
+
+        // Transitions across byte boundaries.
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        assert_eq!(vec.count_transitions(), vec.runs().count() - 1);
+        let vec = BitVec::from_bytes(&[0x80, 0x01, 0xfe]);
+        assert_eq!(vec.count_transitions(), 3);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = BitVec::from_bools(&[true, false, true, true, false]);
+        let b = BitVec::from_bytes_with_len(&[0x06, 0x04], 11);
+
+        let union = a.union_grow(&b);
+        assert_eq!(union.len(), 11);
+        assert_eq!(union.as_bytes(), &[0x0f, 0x04]);
+        assert_eq!(b.union_grow(&a), union);
+
+        let xor = a.xor_grow(&b);
+        assert_eq!(xor.len(), 11);
+        assert_eq!(xor.as_bytes(), &[0x0b, 0x04]);
+        assert_eq!(b.xor_grow(&a), xor);
+
+        let intersection = a.intersect_trunc(&b);
+        assert_eq!(intersection.len(), 5);
+        assert_eq!(intersection.as_bytes(), &[0x04]);
+        assert_eq!(b.intersect_trunc(&a), intersection);
+
+        assert_eq!(a.union_grow(&BitVec::new()), a);
+        assert_eq!(a.intersect_trunc(&BitVec::new()), BitVec::new());
+    }
+
+    #[test]
+    #[cfg(feature = "crc")]
+    fn test_crc32() {
+        // Standard check value of CRC-32/ISO-HDLC.
+        assert_eq!(BitVec::from_bytes(b"123456789").crc32(), 0xcbf43926);
+
+        let a = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 21);
+        let mut b = BitVec::from_bools(&a.to_bool_vec());
+        assert_eq!(a, b);
+        assert_eq!(a.crc32(), b.crc32());
+        b.set(20, !b[20]);
+        assert_ne!(a.crc32(), b.crc32());
+    }
+
+    #[test]
+    fn test_retain_indices() {
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5]);
+        vec.retain_indices(|i| i % 2 == 0);
+        assert_eq!(vec.len(), 8);
+        assert_eq!(vec.as_bytes(), &[0x3b]);
+
+        let mut vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        vec.retain_indices(|i| !(3..=20).contains(&i));
+        assert_eq!(vec, BitVec::from_bools(&[true, true, true, true, true, false]));
+        vec.debug_assert_invariants();
+        vec.retain_indices(|_| true);
+        assert_eq!(vec.len(), 6);
+        vec.retain_indices(|_| false);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_extend_width() {
+        let mut vec = BitVec::from_u64(0b1000, 4);
+        vec.sign_extend(8);
+        assert_eq!(vec.to_u64(), Some(0b1111_1000));
+        vec.sign_extend(8);
+        assert_eq!(vec.len(), 8);
+
+        let mut vec = BitVec::from_u64(0b0101, 4);
+        vec.sign_extend(20);
+        assert_eq!(vec.to_u64(), Some(0b0101));
+        assert_eq!(vec.len(), 20);
+
+        let mut vec = BitVec::from_u64(0b1000, 4);
+        vec.zero_extend(12);
+        assert_eq!(vec.to_u64(), Some(0b1000));
+        assert_eq!(vec.len(), 12);
+
+        let mut vec = BitVec::new();
+        vec.sign_extend(3);
+        assert_eq!(vec, BitVec::from_elem(3, false));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected length 3 >= 4 to extend to.")]
+    fn test_sign_extend_shorter() {
+        BitVec::from_u64(0b1000, 4).sign_extend(3);
+    }
+
+    #[test]
+    fn test_iter_indexed() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        let expected: Vec<(usize, bool)> = (0..vec.len()).zip(vec.iter()).collect();
+        assert_eq!(vec.iter_indexed().collect::<Vec<_>>(), expected);
+        assert_eq!(vec.iter_indexed().rev().collect::<Vec<_>>(),
+                   expected.iter().rev().cloned().collect::<Vec<_>>());
+
+        let mut iter = vec.iter_indexed();
+        assert_eq!(iter.len(), 24);
+        assert_eq!(iter.nth(4), Some((4, false)));
+        assert_eq!(iter.next_back(), Some((23, false)));
+        assert_eq!(iter.len(), 18);
+        assert_eq!(vec.iter().len(), 24);
+        assert_eq!(vec.clone().into_iter().skip(3).len(), 21);
+    }
+
+    #[test]
+    fn test_longest_run() {
+        let vec = BitVec::from_rle(&[(false, 1), (true, 2), (false, 3), (true, 5), (false, 1), (true, 3)]);
+        assert_eq!(vec.longest_run(true), (6, 5));
+        assert_eq!(vec.longest_run(false), (3, 3));
+
+        let vec = BitVec::from_rle(&[(true, 4), (false, 2), (true, 4)]);
+        assert_eq!(vec.longest_run(true), (0, 4));
+        assert_eq!(BitVec::from_elem(10, false).longest_run(true), (0, 0));
+        assert_eq!(BitVec::new().longest_run(false), (0, 0));
+    }
+
+    #[test]
+    fn test_byte_popcounts() {
+        assert_eq!(BitVec::from_bytes(&[0xff, 0x0f]).byte_popcounts(), [8, 4]);
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 21);
+        let counts = vec.byte_popcounts();
+        assert_eq!(counts, [7, 4, 2]);
+        assert_eq!(counts.iter().map(|&c| c as usize).sum::<usize>(), vec.count_ones());
+        assert!(BitVec::new().byte_popcounts().is_empty());
+    }
+
+    #[test]
+    fn test_set_indices() {
+        let mut vec = BitVec::from_elem(16, false);
+        vec.set_indices(&[1, 5, 13], true);
+        assert_eq!(vec.as_bytes(), &[0x22, 0x20]);
+        vec.set_indices(&[5, 5, 0], false);
+        assert_eq!(vec.as_bytes(), &[0x02, 0x20]);
+        vec.set_indices(&[], true);
+        assert_eq!(vec.as_bytes(), &[0x02, 0x20]);
+    }
+
+    #[test]
+    fn test_set_indices_out_of_bounds() {
+        extern crate std;
+        // Check that nothing was set before the out-of-bounds index was detected.
+        let mut vec = BitVec::from_elem(16, false);
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            vec.set_indices(&[1, 16, 3], true);
+        }));
+        assert!(result.is_err());
+        assert_eq!(vec.count_ones(), 0);
+    }
+
+    #[test]
+    fn test_eq_prefix() {
+        let long = BitVec::from_bytes(&[0xef, 0xa5]);
+        let short = BitVec::from_bytes_with_len(&[0xef, 0xa5], 10);
+        assert!(short.eq_prefix(&long));
+        assert!(long.eq_prefix(&short));
+        assert!(long.eq_prefix(&BitVec::new()));
+
+        let mut other = short.clone();
+        other.set(9, true);
+        assert!(!other.eq_prefix(&long));
+        assert!(!long.eq_prefix(&other));
+    }
+
+    #[test]
+    fn test_bitwise_ops() {
+        let a = BitVec::from_bytes_with_len(&[0xef, 0xa5], 12);
+        let b = BitVec::from_bytes_with_len(&[0x5a, 0x3c], 12);
+        assert_eq!((&a & &b).as_bytes(), &[0x4a, 0x04]);
+        assert_eq!((&a | &b).as_bytes(), &[0xff, 0x0d]);
+        assert_eq!((&a ^ &b).as_bytes(), &[0xb5, 0x09]);
+        assert_eq!(a.clone() & b.clone(), &a & &b);
+
+        let mut c = a.clone();
+        c ^= &b;
+        c ^= &b;
+        assert_eq!(c, a);
+        c |= &b;
+        c &= &b;
+        assert_eq!(c, b);
+
+        let not = !a.clone();
+        assert_eq!(not.as_bytes(), &[0x10, 0x0a]);
+        assert_eq!(!not, a);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected equal lengths to and, got 12 and 16.")]
+    fn test_bitwise_ops_unequal_lengths() {
+        let _ = &BitVec::from_elem(12, true) & &BitVec::from_elem(16, true);
+    }
+
+    #[test]
+    fn test_try_push() {
+        let mut vec = BitVec::new();
+        for i in 0..100 {
+            assert!(vec.try_push(i % 3 == 0).is_ok());
+        }
+        assert_eq!(vec.len(), 100);
+        assert_eq!(vec.count_ones(), 34);
+        assert_eq!(vec.as_bytes()[0], 0x49);
+
+        // Allocation failures surface as errors; a capacity overflow is the reliable way to get one.
+        assert!(vec.try_reserve(usize::MAX).is_err());
+        assert!(vec.try_reserve(64).is_ok());
+        assert!(vec.capacity() >= 164);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut vec = BitVec::from_bytes_with_len(&[0xef, 0xa5], 13);
+        let mut other = BitVec::from_bytes_with_len(&[0x71, 0x5a], 11);
+        let expected: BitVec = vec.iter().chain(other.iter()).collect();
+        vec.append(&mut other);
+        assert_eq!(vec, expected);
+        assert_eq!(vec.len(), 24);
+        assert!(other.is_empty());
+        vec.debug_assert_invariants();
+
+        let mut other = BitVec::from_bytes_with_len(&[0x0f], 5);
+        vec.append(&mut other);
+        assert_eq!(vec.len(), 29);
+        assert_eq!(vec.as_bytes()[3], 0x0f);
+        vec.append(&mut BitVec::new());
+        assert_eq!(vec.len(), 29);
+        vec.debug_assert_invariants();
+    }
+
+    #[test]
+    fn test_concat() {
+        let parts = vec![
+            BitVec::from_bytes(&[0xef]),
+            BitVec::from_bytes_with_len(&[0x15], 5),
+            BitVec::from_bytes_with_len(&[0xff, 0x05], 11),
+        ];
+        let expected: BitVec = parts.iter().flat_map(|part| part.iter()).collect();
+        let vec: BitVec = parts.clone().into_iter().collect();
+        assert_eq!(vec.len(), 24);
+        assert_eq!(vec, expected);
+        assert_eq!(vec.as_bytes(), &[0xef, 0xf5, 0xbf]);
+
+        let mut vec = BitVec::from_bools(&[true]);
+        vec.extend(parts);
+        assert_eq!(vec.len(), 25);
+        assert_eq!(vec.iter().skip(1).collect::<BitVec>(), expected);
+    }
+
+    #[test]
+    fn test_iter_fused() {
+        fn assert_fused<I: core::iter::FusedIterator>(_: &I) {}
+
+        let vec = BitVec::from_bools(&[true, false]);
+        let mut iter = vec.iter();
+        assert_fused(&iter);
+        assert_eq!(iter.by_ref().count(), 2);
+        for _ in 0..3 { assert_eq!(iter.next(), None); }
+        assert_eq!(iter.next_back(), None);
+
+        let mut iter = vec.into_iter();
+        assert_fused(&iter);
+        assert_eq!(iter.next_back(), Some(false));
+        assert_eq!(iter.next(), Some(true));
+        for _ in 0..3 { assert_eq!(iter.next(), None); }
+    }
+
+    #[test]
+    fn test_count_ones_masked() {
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let mask = BitVec::from_bytes_with_len(&[0x0f, 0xff, 0x0c], 20);
+        // 0xef & 0x0f = 0x0f, 0xa5 & 0xff = 0xa5, 0x01 & 0x0c = 0x00.
+        assert_eq!(vec.count_ones_masked(&mask), 8);
+        assert_eq!(vec.count_ones_masked(&mask), (&vec & &mask).count_ones());
+        assert_eq!(vec.count_ones_masked(&vec), vec.count_ones());
+        assert_eq!(vec.count_ones_masked(&BitVec::from_elem(20, false)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected equal lengths to mask, got 20 and 24.")]
+    fn test_count_ones_masked_unequal_lengths() {
+        BitVec::from_elem(20, true).count_ones_masked(&BitVec::from_elem(24, true));
+    }
+
+    #[test]
+    fn test_get_many() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5]);
+        assert_eq!(vec.get_many([0, 8, 15]), Some([true, true, true]));
+        assert_eq!(vec.get_many([4, 9, 4]), Some([false, false, false]));
+        assert_eq!(vec.get_many([]), Some([]));
+        assert_eq!(vec.get_many([0, 16]), None);
+    }
+
+    #[test]
+    fn test_clear_range() {
+        let mut vec = BitVec::from_elem(20, true);
+        vec.clear_range(5..14);
+        assert_eq!(format!("{}", vec), "11111... ......11 1111");
+        assert_eq!(vec.as_bytes(), &[0x1f, 0xc0, 0x0f]);
+
+        let mut vec = BitVec::from_elem(20, true);
+        vec.clear_range(2..6);
+        assert_eq!(vec.as_bytes(), &[0xc3, 0xff, 0x0f]);
+        vec.clear_range(8..16);
+        assert_eq!(vec.as_bytes(), &[0xc3, 0x00, 0x0f]);
+        vec.clear_range(10..10);
+        vec.clear_range(16..20);
+        assert_eq!(vec.as_bytes(), &[0xc3, 0x00, 0x00]);
+        vec.clear_range(0..20);
+        assert_eq!(vec.count_ones(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Range [5, 21) out of bounds [0, 20)")]
+    fn test_clear_range_out_of_bounds() {
+        BitVec::from_elem(20, true).clear_range(5..21);
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let mut vec = BitVec::from_elem(21, true);
+        assert_eq!(vec.used_bytes(), 3);
+        assert!(vec.heap_size() >= 3);
+        vec.reserve(80);
+        assert_eq!(vec.used_bytes(), 3);
+        assert!(vec.heap_size() >= 13);
+        assert_eq!(vec.heap_size() * 8, vec.capacity());
+        assert_eq!(BitVec::new().heap_size(), 0);
+        assert_eq!(BitVec::new().used_bytes(), 0);
+    }
+
+    #[test]
+    fn test_double() {
+        let original = BitVec::from_bytes_with_len(&[0xef, 0xa5], 12);
+        let mut vec = original.clone();
+        vec.double();
+        assert_eq!(vec.len(), 24);
+        assert_eq!(vec.iter().take(12).collect::<BitVec>(), original);
+        assert_eq!(vec.iter().skip(12).collect::<BitVec>(), original);
+        assert_eq!(vec.as_bytes(), &[0xef, 0xf5, 0x5e]);
+        vec.debug_assert_invariants();
+
+        let mut vec = BitVec::from_bytes(&[0x71]);
+        vec.double();
+        assert_eq!(vec.as_bytes(), &[0x71, 0x71]);
+        let mut vec = BitVec::new();
+        vec.double();
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        use serde_test::{assert_de_tokens, assert_tokens, Token};
+
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5], 12);
+        assert_tokens(&vec, &[
+            Token::Struct { name: "BitVec", len: 2 },
+            Token::Str("nbits"), Token::U64(12),
+            Token::Str("vec"), Token::Seq { len: Some(2) }, Token::U8(0xef), Token::U8(0x05), Token::SeqEnd,
+            Token::StructEnd,
+        ]);
+        assert_de_tokens(&vec, &[
+            Token::Seq { len: Some(2) },
+            Token::U64(12),
+            Token::Seq { len: Some(2) }, Token::U8(0xef), Token::U8(0x05), Token::SeqEnd,
+            Token::SeqEnd,
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_rejects_invalid() {
+        use serde_test::{assert_de_tokens_error, Token};
+
+        let tokens = |nbits, bytes: &'static [Token]| {
+            let mut tokens = vec![
+                Token::Struct { name: "BitVec", len: 2 },
+                Token::Str("nbits"), Token::U64(nbits),
+                Token::Str("vec"), Token::Seq { len: Some(bytes.len()) },
+            ];
+            tokens.extend_from_slice(bytes);
+            tokens.extend_from_slice(&[Token::SeqEnd, Token::StructEnd]);
+            tokens
+        };
+        assert_de_tokens_error::<BitVec>(&tokens(20, &[Token::U8(0xef), Token::U8(0x05)]),
+                                         "Expected #bytes 2 in vec == ceil(#bits 20 / 8).");
+        assert_de_tokens_error::<BitVec>(&[
+            Token::Struct { name: "BitVec", len: 2 },
+            Token::Str("nbits"), Token::U64(4),
+            Token::Str("vec"), Token::Seq { len: Some(2) }, Token::U8(0xef), Token::U8(0x05),
+        ], "invalid length 2, expected a sequence of at most 1 bytes");
+        assert_de_tokens_error::<BitVec>(&tokens(12, &[Token::U8(0xef), Token::U8(0xa5)]),
+                                         "Expected the unused bits beyond #bits 12 to be 0.");
+        assert_de_tokens_error::<BitVec>(
+            &[Token::Struct { name: "BitVec", len: 1 }, Token::Str("nbits"), Token::U64(12), Token::StructEnd],
+            "missing field `vec`");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_with_limit() {
+        use serde::{Deserialize, Deserializer};
+        use serde_test::{assert_de_tokens, assert_de_tokens_error, Token};
+
+        #[derive(Debug, PartialEq)]
+        struct Limited(BitVec);
+        impl<'de> Deserialize<'de> for Limited {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                BitVec::deserialize_with_limit(deserializer, 12).map(Limited)
+            }
+        }
+
+        let tokens = |nbits| [
+            Token::Struct { name: "BitVec", len: 2 },
+            Token::Str("nbits"), Token::U64(nbits),
+            Token::Str("vec"), Token::Seq { len: Some(2) }, Token::U8(0xef), Token::U8(0x05), Token::SeqEnd,
+            Token::StructEnd,
+        ];
+        assert_de_tokens(&Limited(BitVec::from_bytes_with_len(&[0xef, 0xa5], 12)), &tokens(12));
+        assert_de_tokens_error::<Limited>(&tokens(13)[..3], "Expected #bits 13 <= limit 12.");
+        // With the bytes first, the limit caps them before `nbits` is read.
+        assert_de_tokens_error::<Limited>(&[
+            Token::Struct { name: "BitVec", len: 2 },
+            Token::Str("vec"), Token::Seq { len: Some(3) }, Token::U8(0xef), Token::U8(0x05), Token::U8(0),
+        ], "invalid length 3, expected a sequence of at most 2 bytes");
+    }
+
+    #[test]
+    fn test_bytes_needed() {
+        const BUFFER_LEN: usize = BitVec::bytes_needed(21);
+        let buffer = [0xffu8; BUFFER_LEN];
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(BitVec::from_bytes_with_len(&buffer, 21).as_bytes().len(), BUFFER_LEN);
+        assert_eq!(BitVec::bytes_needed(0), 0);
+        assert_eq!(BitVec::bytes_needed(usize::MAX), usize::MAX / 8 + 1);
+    }
+
+    #[test]
+    fn test_cmp_as_uint() {
+        use core::cmp::Ordering;
+
+        let a = BitVec::from_u64(0b0010, 4);
+        let b = BitVec::from_u64(0b0001, 4);
+        assert_eq!(a.cmp_as_uint(&b), Ordering::Greater);
+        assert_eq!(b.cmp_as_uint(&a), Ordering::Less);
+        assert_eq!(a.cmp_as_uint(&a), Ordering::Equal);
+
+        let a = BitVec::from_u64(0x1ff, 20);
+        let b = BitVec::from_u64(0x10000, 20);
+        assert_eq!(a.cmp_as_uint(&b), Ordering::Less);
+        assert_eq!(BitVec::new().cmp_as_uint(&BitVec::new()), Ordering::Equal);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected equal lengths to compare, got 4 and 5.")]
+    fn test_cmp_as_uint_unequal_lengths() {
+        BitVec::from_u64(0, 4).cmp_as_uint(&BitVec::from_u64(0, 5));
+    }
+
+    #[test]
+    fn test_bit_grid() {
+        use super::BitGrid;
+
+        let mut grid = BitGrid::new(3, 5);
+        assert_eq!((grid.rows(), grid.cols()), (3, 5));
+        grid.set(0, 0, true);
+        grid.set(1, 4, true);
+        grid.set(2, 2, true);
+        assert!(grid.get(1, 4));
+        assert!(!grid.get(1, 3));
+        assert_eq!(grid.row_iter(1).collect::<BitVec>(), BitVec::from_u64(0b10000, 5));
+        assert_eq!(grid.row_iter(2).collect::<BitVec>(), BitVec::from_u64(0b00100, 5));
+        assert_eq!(grid.row_iter(2).len(), 5);
+
+        let vec = grid.clone().into_bitvec();
+        assert_eq!(vec.len(), 15);
+        assert_eq!(vec.as_bytes(), &[0x01, 0x12]);
+        assert_eq!(BitGrid::from_bitvec(vec, 5), grid);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index (1, 5) out of bounds [0, 3) x [0, 5)")]
+    fn test_bit_grid_column_out_of_bounds() {
+        super::BitGrid::new(3, 5).get(1, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index (3, 0) out of bounds [0, 3) x [0, 5)")]
+    fn test_bit_grid_row_out_of_bounds() {
+        super::BitGrid::new(3, 5).set(3, 0, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected at most usize::MAX bits in a")]
+    fn test_bit_grid_too_large() {
+        super::BitGrid::new(usize::MAX / 2, 3);
+    }
+
+    #[test]
+    #[cfg(feature = "roaring")]
+    fn test_roaring_bytes() {
+        let mut vec = BitVec::from_elem(100_000, false);
+        vec.set_indices(&[0, 7, 4096, 65535, 65536, 99_999], true);
+        let bytes = vec.to_roaring_bytes();
+        assert_eq!(BitVec::from_roaring_bytes(&bytes, 100_000).unwrap(), vec);
+        assert!(BitVec::from_roaring_bytes(&bytes, 99_999).is_err());
+        assert!(BitVec::from_roaring_bytes(&bytes[..bytes.len() - 1], 100_000).is_err());
+
+        let empty = BitVec::from_elem(10, false);
+        assert_eq!(BitVec::from_roaring_bytes(&empty.to_roaring_bytes(), 10).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_xor_diff() {
+        // Pseudo-random bits from a linear congruential generator.
+        let mut state = 12345u32;
+        let mut random_vec = |len| -> BitVec {
+            (0..len).map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                state & 0x10000 != 0
+            }).collect()
+        };
+        for len in [0, 1, 13, 64, 100] {
+            let a = random_vec(len);
+            let base = random_vec(len);
+            let diff = a.xor_diff(&base);
+            let mut restored = base.clone();
+            restored.apply_xor_diff(&diff);
+            assert_eq!(restored, a);
+            assert_eq!(diff.count_ones(), (0..len).filter(|&i| a[i] != base[i]).count());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected equal lengths to xor, got 12 and 13.")]
+    fn test_apply_xor_diff_unequal_lengths() {
+        BitVec::from_elem(12, true).apply_xor_diff(&BitVec::from_elem(13, true));
+    }
+
+    #[test]
+    fn test_extract_u64() {
+        let mut vec = BitVec::from_bools(&[true, false, true]);
+        vec.push_bits(0xabcde, 20);
+        vec.push_bits(u64::MAX, 64);
+        assert_eq!(vec.extract_u64(3, 20), 0xabcde);
+        assert_eq!(vec.extract_u64(23, 64), u64::MAX);
+        assert_eq!(vec.extract_u64(0, 3), 0b101);
+        assert_eq!(vec.extract_u64(5, 0), 0);
+    }
+
+    #[test]
+    fn test_extract_u64_msb0() {
+        let mut vec = BitVec::from_bools(&[true, false, true]);
+        vec.push_bits_msb0(0xabcde, 20);
+        vec.push_bits_msb0(0x8000_0000_0000_0001, 64);
+        assert_eq!(vec.extract_u64_msb0(3, 20), 0xabcde);
+        assert_eq!(vec.extract_u64_msb0(23, 64), 0x8000_0000_0000_0001);
+        assert_eq!(vec.extract_u64_msb0(0, 3), 0b101);
+
+        // The two orders are reverses of each other within a field.
+        let mut vec = BitVec::new();
+        vec.push_bits_msb0(0b1101, 4);
+        assert_eq!(vec, BitVec::from_bools(&[true, true, false, true]));
+        assert_eq!(vec.extract_u64(0, 4), 0b1011);
+        vec.push_bits(0b1101, 4);
+        assert_eq!(vec.extract_u64_msb0(4, 4), 0b1011);
+    }
+
+    #[test]
+    #[should_panic(expected = "Range [3, 24) out of bounds [0, 23)")]
+    fn test_extract_u64_out_of_bounds() {
+        BitVec::from_elem(23, true).extract_u64(3, 21);
+    }
+
+    #[test]
+    fn test_leading_trailing_ones() {
+        let mut vec = BitVec::from_bytes(&[0x0f]);
+        assert_eq!(vec.trailing_ones(), 4);
+        assert_eq!(vec.leading_ones(), 0);
+        vec.truncate(4);
+        assert_eq!(vec.leading_ones(), 4);
+        assert_eq!(vec.trailing_ones(), 4);
+
+        let vec = BitVec::from_bytes_with_len(&[0xff, 0xf7, 0x1f], 21);
+        assert_eq!(vec.trailing_ones(), 11);
+        assert_eq!(vec.leading_ones(), 9);
+        let vec = BitVec::from_elem(19, true);
+        assert_eq!(vec.trailing_ones(), 19);
+        assert_eq!(vec.leading_ones(), 19);
+        assert_eq!(BitVec::new().leading_ones(), 0);
+        assert_eq!(BitVec::from_bools(&[true, false]).leading_ones(), 0);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let a = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 21);
+        let b: BitVec = a.iter().collect();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        for i in 0..a.len() {
+            let mut c = a.clone();
+            c.set(i, !c[i]);
+            assert_ne!(a.fingerprint(), c.fingerprint());
+        }
+        // The length contributes even when the bytes are equal.
+        assert_ne!(BitVec::from_elem(3, false).fingerprint(), BitVec::from_elem(4, false).fingerprint());
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let tail = vec.split_off(5);
+        assert_eq!(vec, BitVec::from_bools(&[true, true, true, true, false]));
+        assert_eq!(tail.len(), 15);
+        assert_eq!(tail.as_bytes(), &[0x2f, 0x0d]);
+        assert_eq!(vec.split_off(5), BitVec::new());
+    }
+
+    #[test]
+    fn test_truncate_front() {
+        for n in [0, 1, 5, 8, 13, 19, 20, 25] {
+            let mut vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+            let expected = vec.clone().split_off(n.min(20));
+            vec.truncate_front(n);
+            assert_eq!(vec, expected);
+            vec.debug_assert_invariants();
+        }
+    }
+
+    #[test]
+    fn test_bytes_with_tail() {
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5], 16);
+        let (bytes, tail) = vec.bytes_with_tail();
+        assert_eq!(bytes.collect::<Vec<_>>(), vec![0xef, 0xa5]);
+        assert_eq!(tail, 0);
+
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5], 13);
+        let (bytes, tail) = vec.bytes_with_tail();
+        assert_eq!(bytes.collect::<Vec<_>>(), vec![0xef, 0x05]);
+        assert_eq!(tail, 5);
+
+        let vec = BitVec::new();
+        let (bytes, tail) = vec.bytes_with_tail();
+        assert_eq!(bytes.count(), 0);
+        assert_eq!(tail, 0);
+    }
+
+    #[test]
+    fn test_toggle_range() {
+        let mut vec = BitVec::from_elem(20, false);
+        vec.toggle_range(3..17);
+        assert_eq!(vec.as_bytes(), &[0xf8, 0xff, 0x01]);
+
+        let original = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let mut vec = original.clone();
+        vec.toggle_range(3..17);
+        assert_eq!(vec.as_bytes(), &[0x17, 0x5a, 0x00]);
+        vec.toggle_range(3..17);
+        assert_eq!(vec.as_bytes(), original.as_bytes());
+        vec.toggle_range(2..6);
+        assert_eq!(vec.as_bytes(), &[0xd3, 0xa5, 0x01]);
+        vec.toggle_range(10..10);
+        vec.toggle_range(0..20);
+        assert_eq!(vec.as_bytes(), &[0x2c, 0x5a, 0x0e]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Range [5, 21) out of bounds [0, 20)")]
+    fn test_toggle_range_out_of_bounds() {
+        BitVec::from_elem(20, true).toggle_range(5..21);
+    }
+
+    #[test]
+    fn test_bitvec_builder() {
+        use super::{BitVecBuilder, NotIncreasing};
+
+        let mut expected = BitVec::from_elem(21, false);
+        expected.set_indices(&[2, 9, 20], true);
+
+        let mut builder = BitVecBuilder::new();
+        assert_eq!(builder.push_position(2), Ok(()));
+        assert_eq!(builder.push_position(9), Ok(()));
+        assert_eq!(builder.push_position(9), Err(NotIncreasing { position: 9, last: 9 }));
+        assert_eq!(builder.push_position(3), Err(NotIncreasing { position: 3, last: 9 }));
+        assert_eq!(builder.push_position(20), Ok(()));
+        assert_eq!(builder.build(), expected);
+
+        let mut builder = BitVecBuilder::new().allow_duplicates(true);
+        for position in [2, 9, 9, 20] {
+            builder.push_position(position).unwrap();
+        }
+        assert_eq!(builder.build(), expected);
+        assert_eq!(BitVecBuilder::new().build(), BitVec::new());
+        assert_eq!(format!("{}", NotIncreasing { position: 3, last: 9 }),
+                   "Position 3 does not follow previous position 9");
+    }
+
+    #[test]
+    #[should_panic(expected = "< usize::MAX.")]
+    fn test_bitvec_builder_max_position() {
+        let _ = super::BitVecBuilder::new().push_position(usize::MAX);
+    }
+
+    #[test]
+    fn test_as_bit_slice() {
+        use super::BitSlice;
+
+        fn count_ones(bits: BitSlice<'_>) -> usize {
+            bits.iter().filter(|&bit| bit).count()
+        }
+
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let slice = vec.as_bit_slice();
+        assert_eq!(slice.len(), 20);
+        assert_eq!(slice.get(4), Some(false));
+        assert_eq!(slice.get(20), None);
+        assert_eq!(slice.iter().collect::<BitVec>(), vec);
+        assert_eq!(count_ones(vec.as_bit_slice()), vec.count_ones());
+        assert!(BitVec::new().as_bit_slice().is_empty());
+    }
+
+    #[test]
+    fn test_prefix_set() {
+        use super::PrefixSet;
+
+        let code = |bits: &str| bits.chars().map(|c| c == '1').collect::<BitVec>();
+        let mut set = PrefixSet::new();
+        assert!(!set.insert(BitVec::new()));
+        assert!(set.is_empty());
+        for bits in ["0", "10", "110", "111"] {
+            assert!(set.insert(code(bits)));
+        }
+        assert!(!set.insert(code("1")));
+        assert!(!set.insert(code("1101")));
+        assert!(!set.insert(code("10")));
+        assert!(!set.insert(BitVec::new()));
+        assert_eq!(set.len(), 4);
+
+        let stream = code("0101111100110");
+        let mut decoded = Vec::new();
+        let mut pos = 0;
+        while let Some((code, len)) = set.longest_match(&stream, pos) {
+            decoded.push(set.iter().position(|c| c == code).unwrap());
+            pos += len;
+        }
+        assert_eq!(decoded, vec![0, 1, 3, 2, 0, 2]);
+        assert_eq!(pos, stream.len());
+        assert_eq!(set.longest_match(&code("11"), 0), None);
+        assert_eq!(set.longest_match(&stream, 20), None);
+    }
+
+    #[test]
+    fn test_bit_reverse_index() {
+        assert_eq!(BitVec::bit_reverse_index(1, 3), 4);
+        assert_eq!(BitVec::bit_reverse_index(6, 3), 3);
+        assert_eq!(BitVec::bit_reverse_index(0b1011, 4), 0b1101);
+        assert_eq!(BitVec::bit_reverse_index(0b1_0011, 4), 0b1100);
+        assert_eq!(BitVec::bit_reverse_index(5, 0), 0);
+        assert_eq!(BitVec::bit_reverse_index(1, usize::BITS), 1 << (usize::BITS - 1));
+    }
+
+    #[test]
+    fn test_permute_bit_reversed() {
+        let original = BitVec::from_bytes(&[0xef, 0xa5]);
+        let mut vec = original.clone();
+        vec.permute_bit_reversed(4);
+        for i in 0..16 {
+            assert_eq!(vec[BitVec::bit_reverse_index(i, 4)], original[i]);
+        }
+        vec.permute_bit_reversed(4);
+        assert_eq!(vec, original);
+
+        let mut vec = BitVec::from_bools(&[true]);
+        vec.permute_bit_reversed(0);
+        assert_eq!(vec, BitVec::from_bools(&[true]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected #bits 12 == 2^4.")]
+    fn test_permute_bit_reversed_bad_len() {
+        BitVec::from_elem(12, true).permute_bit_reversed(4);
+    }
+
+    #[test]
+    fn test_bit_set() {
+        extern crate std;
+        use core::hash::{Hash, Hasher};
+        use std::collections::hash_map::DefaultHasher;
+        use super::BitSet;
+
+        fn hash(set: &BitSet) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            set.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut short = BitVec::from_elem(10, false);
+        short.set_indices(&[1, 7, 9], true);
+        let mut long = BitVec::from_elem(16, false);
+        long.set_indices(&[1, 7, 9], true);
+        assert_ne!(short, long);
+        let (short, long) = (BitSet::new(short), BitSet::from(long));
+        assert_eq!(short, long);
+        assert_eq!(hash(&short), hash(&long));
+        assert!(long.contains(9) && !long.contains(8) && !long.contains(16));
+
+        assert_eq!(BitSet::new(BitVec::from_elem(20, false)), BitSet::default());
+        assert_eq!(hash(&BitSet::new(BitVec::from_elem(20, false))), hash(&BitSet::default()));
+        assert_ne!(BitSet::new(BitVec::from_elem(9, true)), short);
+    }
+
+    #[test]
+    fn test_bit_pairs() {
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let pairs = vec.bit_pairs().collect::<Vec<_>>();
+        assert_eq!(pairs.len(), 19);
+        assert_eq!(pairs, (0..19).map(|i| (vec[i], vec[i + 1])).collect::<Vec<_>>());
+        assert_eq!(&pairs[3..6], &[(true, false), (false, true), (true, true)]);
+        assert_eq!(BitVec::new().bit_pairs().count(), 0);
+        assert_eq!(BitVec::from_bools(&[true]).bit_pairs().count(), 0);
+        assert_eq!(BitVec::from_bools(&[true, false]).bit_pairs().collect::<Vec<_>>(),
+                   vec![(true, false)]);
+    }
+
+    #[test]
+    fn test_to_bool_array() {
+        let vec = BitVec::from_bools(&[true, false, true, true]);
+        assert_eq!(vec.to_bool_array::<4>(), Some([true, false, true, true]));
+        assert_eq!(vec.to_bool_array::<3>(), None);
+        assert_eq!(vec.to_bool_array::<5>(), None);
+        assert_eq!(BitVec::new().to_bool_array::<0>(), Some([]));
+    }
+
+    #[test]
+    fn test_count_ones_words() {
+        // Pseudo-random bytes, truncated to lengths that exercise both the words and the tail.
+        let bytes = (0..10_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect::<Vec<_>>();
+        for nbits in [0, 7, 63, 64, 65, 1000, 79_997, 80_000] {
+            let vec = BitVec::from_bytes_with_len(&bytes, nbits);
+            let naive = vec.as_bytes().iter().map(|byte| byte.count_ones() as usize).sum::<usize>();
+            assert_eq!(vec.count_ones(), naive);
+        }
+    }
+
+    #[test]
+    fn test_set_len() {
+        let mut vec = BitVec::new();
+        vec.reserve(20);
+        let bytes = [0xef, 0xa5, 0x01];
+        unsafe {
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), vec.as_mut_ptr(), bytes.len());
+            vec.set_len(20);
+        }
+        assert_eq!(vec, BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20));
+        unsafe { vec.set_len(16) };
+        assert_eq!(vec.as_bytes(), &[0xef, 0xa5]);
+        unsafe { vec.set_len(0) };
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn test_changed_positions() {
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let mut other = vec.clone();
+        for index in [19, 3, 10] {
+            other.set(index, !other[index]);
+        }
+        assert_eq!(vec.changed_positions(&other), vec![3, 10, 19]);
+        assert_eq!(other.changed_positions(&vec), vec![3, 10, 19]);
+        assert_eq!(vec.changed_positions(&vec), Vec::<usize>::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected equal lengths to compare, got 20 and 24.")]
+    fn test_changed_positions_unequal_lengths() {
+        BitVec::from_elem(20, true).changed_positions(&BitVec::from_elem(24, true));
+    }
+
+    #[test]
+    fn test_from_hex() {
+        let vec = BitVec::from_hex("efA571").unwrap();
+        assert_eq!(vec, BitVec::from_bytes(&[0xef, 0xa5, 0x71]));
+        assert_eq!(BitVec::from_hex(""), Some(BitVec::new()));
+        assert_eq!(BitVec::from_hex("efa"), None);
+        assert_eq!(BitVec::from_hex("efg5"), None);
+        assert_eq!(BitVec::from_hex("+f"), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_hex_reader() {
+        use std::io::{Cursor, ErrorKind};
+
+        let hex = "0123456789abcdefABCDEF".repeat(500);
+        let vec = BitVec::from_hex_reader(Cursor::new(&hex)).unwrap();
+        assert_eq!(vec, BitVec::from_hex(&hex).unwrap());
+        assert_eq!(vec.len(), hex.len() * 4);
+        assert_eq!(BitVec::from_hex_reader(Cursor::new("")).unwrap(), BitVec::new());
+
+        let err = BitVec::from_hex_reader(Cursor::new("efa")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Expected an even number of hex digits, got 3.");
+        let err = BitVec::from_hex_reader(Cursor::new("ef\n")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Expected a hex digit, got '\\n'.");
+    }
+
+    #[test]
+    fn test_pop_and_maybe_shrink() {
+        use super::POP_SHRINK_RATIO;
+
+        let mut vec = BitVec::from_elem(1000, true);
+        let initial_capacity = vec.capacity();
+        while (vec.len() - 1) * POP_SHRINK_RATIO >= initial_capacity {
+            assert_eq!(vec.pop_and_maybe_shrink(), Some(true));
+            assert_eq!(vec.capacity(), initial_capacity);
+        }
+        assert_eq!(vec.pop_and_maybe_shrink(), Some(true));
+        assert!(vec.capacity() < initial_capacity);
+        while vec.pop_and_maybe_shrink().is_some() {
+            assert!(vec.capacity() <= (vec.len() * POP_SHRINK_RATIO).max(8));
+        }
+        assert_eq!(vec.capacity(), 0);
+        assert_eq!(vec.pop_and_maybe_shrink(), None);
+    }
+
+    #[test]
+    fn test_be_bytes() {
+        let vec = BitVec::from_bytes(&[0xef, 0xa5, 0x71]);
+        let bytes = vec.to_be_bytes();
+        assert_eq!(bytes, vec![0x71, 0xa5, 0xef]);
+        assert_eq!(BitVec::from_be_bytes(&bytes), vec);
+        assert_eq!(BitVec::from_u64(0x1234, 16).to_be_bytes(), 0x1234u16.to_be_bytes());
+
+        // The padding bits lead the first output byte.
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        assert_eq!(vec.to_be_bytes(), vec![0x01, 0xa5, 0xef]);
+        assert_eq!(BitVec::new().to_be_bytes(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_symbols() {
+        let symbols = [3u64, 31, 0, 17, 8, 22, 1];
+        let mut vec = BitVec::new();
+        for &symbol in &symbols {
+            vec.push_bits(symbol, 5);
+        }
+        assert_eq!(vec.symbols(5).collect::<Vec<_>>(), symbols);
+
+        // The partial final symbol is skipped.
+        vec.push_bits(0b101, 3);
+        assert_eq!(vec.symbols(5).collect::<Vec<_>>(), symbols);
+        assert_eq!(vec.extract_u64(35, 3), 0b101);
+        assert_eq!(vec.symbols(64).count(), 0);
+        assert_eq!(vec.symbols(1).count(), 38);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a symbol width in [1, 64], got 0.")]
+    fn test_symbols_zero_width() {
+        let _ = BitVec::from_elem(8, true).symbols(0);
+    }
+
+    #[test]
+    fn test_eq_bool_slice() {
+        let vec = BitVec::from_bools(&[true, false, true]);
+        assert_eq!(vec, [true, false, true][..]);
+        assert_eq!(vec, &[true, false, true][..]);
+        assert_ne!(vec, [true, false, false][..]);
+        assert_ne!(vec, [true, false][..]);
+        assert_ne!(vec, &[true, false, true, false][..]);
+        assert_eq!(BitVec::new(), [][..]);
+    }
+
+    #[test]
+    fn test_insert_bitvec() {
+        let original = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let field = BitVec::from_bools(&[true, false, false, true, true]);
+        let mut vec = original.clone();
+        vec.insert_bitvec(4, &field);
+        assert_eq!(vec.len(), 25);
+        assert_eq!(vec.iter().take(4).collect::<BitVec>(), original.iter().take(4).collect::<BitVec>());
+        assert_eq!(vec.iter().skip(4).take(5).collect::<BitVec>(), field);
+        assert_eq!(vec.iter().skip(9).collect::<BitVec>(), original.iter().skip(4).collect::<BitVec>());
+        vec.debug_assert_invariants();
+
+        let mut vec = original.clone();
+        vec.insert_bitvec(20, &field);
+        assert_eq!(vec.iter().skip(20).collect::<BitVec>(), field);
+        let mut vec = original.clone();
+        vec.insert_bitvec(7, &BitVec::new());
+        assert_eq!(vec, original);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 21 out of bounds [0, 20]")]
+    fn test_insert_bitvec_out_of_bounds() {
+        BitVec::from_elem(20, true).insert_bitvec(21, &BitVec::from_elem(3, false));
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let original = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let mut vec = original.clone();
+        vec.remove_range(4..12);
+        assert_eq!(format!("{}", vec), "1111.1.1 1...");
+        assert_eq!(vec.as_bytes(), &[0xaf, 0x01]);
+        vec.remove_range(6..6);
+        assert_eq!(vec.len(), 12);
+        vec.remove_range(8..12);
+        assert_eq!(vec.as_bytes(), &[0xaf]);
+        vec.remove_range(0..8);
+        assert!(vec.is_empty());
+
+        let mut vec = original.clone();
+        vec.remove_range(13..20);
+        assert_eq!(vec, original.iter().take(13).collect::<BitVec>());
+    }
+
+    #[test]
+    #[should_panic(expected = "Range [4, 21) out of bounds [0, 20)")]
+    fn test_remove_range_out_of_bounds() {
+        BitVec::from_elem(20, true).remove_range(4..21);
+    }
+
+    #[test]
+    fn test_approximate_density() {
+        // Every byte has 3 of 8 bits set, so any sample gives the exact density.
+        let vec = BitVec::from_bytes(&[0x07, 0x31, 0xa8, 0x0e].repeat(10_000));
+        assert_eq!(vec.approximate_density(100), 0.375);
+
+        // A uniformly random vector has an estimate close to the true density.
+        let bytes = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect::<Vec<_>>();
+        let vec = BitVec::from_bytes(&bytes);
+        let exact = vec.count_ones() as f64 / vec.len() as f64;
+        assert!((vec.approximate_density(1000) - exact).abs() < 0.02);
+
+        // Small vectors are counted exactly, excluding the padding bits.
+        let vec = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        assert_eq!(vec.approximate_density(100), 12.0 / 20.0);
+        assert_eq!(BitVec::new().approximate_density(1), 0.0);
+
+        // A 1 MiB vector with 8192 samples, whose index product overflows a 32-bit `usize`.
+        let vec = BitVec::from_bytes(&[0x07, 0x31, 0xa8, 0x0e].repeat(1 << 18));
+        assert_eq!(vec.approximate_density(8192), 0.375);
+    }
+
+    #[test]
+    fn test_sample_index() {
+        use super::sample_index;
+        assert_eq!(sample_index(0, 100, 10), 0);
+        assert_eq!(sample_index(9, 100, 10), 90);
+        assert_eq!(sample_index(8191, 1 << 20, 8192), 1_048_448);
+        // The product of these counts overflows `usize` on every target.
+        assert_eq!(sample_index(usize::MAX - 1, usize::MAX, usize::MAX - 1), usize::MAX);
+        assert_eq!(sample_index(usize::MAX / 2, usize::MAX - 1, usize::MAX), usize::MAX / 2 - 1);
+    }
+
+    #[test]
+    fn test_collect_unsized_iter() {
+        // `from_fn` reports a size hint of `(0, None)`.
+        let mut i = 0;
+        let vec = core::iter::from_fn(|| {
+            i += 1;
+            if i <= 100_000 { Some(i % 3 == 0) } else { None }
+        }).collect::<BitVec>();
+        assert_eq!(vec.len(), 100_000);
+        assert_eq!(vec.count_ones(), 33_333);
+        assert!(vec.capacity() <= 2 * vec.len());
+
+        // A filter's upper bound is not reserved up front.
+        let vec = (0..1_000_000).filter(|i| i % 100_000 == 0).map(|i| i % 200_000 == 0).collect::<BitVec>();
+        assert_eq!(vec.len(), 10);
+        assert!(vec.capacity() < 1000);
+
+        let mut vec = BitVec::from_elem(3, true);
+        vec.extend((0..1_000_000).filter(|&i| i < 5).map(|_| false));
+        assert_eq!(vec.len(), 8);
+        assert!(vec.capacity() < 1000);
+    }
+
+    #[test]
+    fn test_try_swap() {
+        use super::OutOfBounds;
+
+        let original = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let mut vec = original.clone();
+        assert_eq!(vec.try_swap(4, 16), Ok(()));
+        assert_eq!((vec[4], vec[16]), (true, false));
+        assert_eq!(vec.try_swap(4, 16), Ok(()));
+        assert_eq!(vec, original);
+
+        assert_eq!(vec.try_swap(4, 20), Err(OutOfBounds { index: 20, len: 20 }));
+        assert_eq!(vec.try_swap(25, 4), Err(OutOfBounds { index: 25, len: 20 }));
+        assert_eq!(vec, original);
+    }
+
+    #[test]
+    fn test_bitwise_into() {
+        let a = BitVec::from_bytes_with_len(&[0xef, 0xa5, 0x71], 20);
+        let b = BitVec::from_bytes_with_len(&[0x0f, 0xff, 0x0c], 20);
+        let mut out = BitVec::new();
+        a.and_into(&b, &mut out);
+        assert_eq!(out, &a & &b);
+        let capacity = out.capacity();
+        for _ in 0..10 {
+            a.or_into(&b, &mut out);
+            assert_eq!(out, &a | &b);
+            a.xor_into(&b, &mut out);
+            assert_eq!(out, &a ^ &b);
+            assert_eq!(out.capacity(), capacity);
+        }
+
+        // A longer `out` is shrunk to the operands' length.
+        let mut out = BitVec::from_elem(100, true);
+        a.and_into(&b, &mut out);
+        assert_eq!(out, &a & &b);
+        out.debug_assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected equal lengths to xor, got 20 and 24.")]
+    fn test_bitwise_into_unequal_lengths() {
+        BitVec::from_elem(20, true).xor_into(&BitVec::from_elem(24, true), &mut BitVec::new());
+    }
+
+    #[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+    #[test]
+    fn test_default_unstable() {
+        use super::Global;
+
+        let vec: BitVec = BitVec::default();
+        assert!(vec.is_empty());
+        let vec = BitVec::<Global>::default();
+        assert_eq!(vec.as_bytes(), &[]);
+    }
+
+    #[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+    #[test]
+    fn test_constructors_in() {
+        use super::Global;
+
+        let mut vec = BitVec::new_in(Global);
+        assert_eq!(vec.capacity(), 0);
+        vec.push(true);
+        assert_eq!(vec.as_bytes(), &[0x01]);
+
+        let vec = BitVec::with_capacity_in(9, Global);
+        assert_eq!(vec.len(), 0);
+        assert_eq!(vec.capacity(), 16);
+        assert_eq!(vec, BitVec::new());
+    }
+
+    #[cfg(any(feature = "unstable", feature = "allocator-api2"))]
+    #[test]
+    fn test_nonempty_constructors_in() {
+        use super::Global;
+
+        let vec = BitVec::from_bytes_in(&[0xef, 0xa5], Global);
+        assert_eq!(vec.len(), 16);
+        assert_eq!(vec, BitVec::from_bytes(&[0xef, 0xa5]));
+        assert_eq!(BitVec::from_bytes_in(&[], Global), BitVec::new());
+
+        let vec = BitVec::from_elem_in(11, true, Global);
+        assert_eq!(vec.as_bytes(), &[0xff, 0x07]);
+        assert_eq!(vec, BitVec::from_elem(11, true));
+        vec.debug_assert_invariants();
+    }
+
+    /// An allocator distinct from `Global`, to test custom allocator types.
+    #[cfg(feature = "unstable")]
+    #[derive(Clone, Copy, Default)]
+    struct Pool;
+
+    #[cfg(feature = "unstable")]
+    unsafe impl core::alloc::Allocator for Pool {
+        fn allocate(&self, layout: core::alloc::Layout)
+            -> Result<core::ptr::NonNull<[u8]>, core::alloc::AllocError>
+        {
+            alloc::alloc::Global.allocate(layout)
+        }
+        unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+            alloc::alloc::Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[cfg(all(feature = "serde", feature = "unstable"))]
+    #[test]
+    fn test_serde_custom_allocator() {
+        use serde_test::{assert_de_tokens, Token};
+
+        let mut vec = BitVec::from_bytes_in(&[0xcf, 0x05], Pool);
+        vec.truncate(12);
+        assert_de_tokens(&vec, &[
+            Token::Struct { name: "BitVec", len: 2 },
+            Token::Str("nbits"), Token::U64(12),
+            Token::Str("vec"), Token::Seq { len: Some(2) }, Token::U8(0xcf), Token::U8(0x05), Token::SeqEnd,
+            Token::StructEnd,
+        ]);
+    }
+
+    #[cfg(feature = "unstable")]
+    #[test]
+    fn test_bitwise_ops_mixed_allocators() {
+        let mask = BitVec::from_bytes_in(&[0x0f, 0xf0], Pool);
+        let bitmap = BitVec::from_bytes(&[0xef, 0xa5]);
+        let masked: BitVec<Pool> = &mask & &bitmap;
+        assert_eq!(masked.as_bytes(), &[0x0f, 0xa0]);
+        let mut masked = mask.clone() ^ bitmap.clone();
+        masked ^= &mask;
+        assert_eq!(masked, bitmap);
+    }
+
+    #[cfg(feature = "unstable")]
+    #[test]
+    fn test_xor_diff_custom_allocator() {
+        let a = BitVec::from_bytes_in(&[0xef, 0xa5], Pool);
+        let mut restored = BitVec::from_bytes_in(&[0x0f, 0x10], Pool);
+        let diff = a.xor_diff(&restored);
+        assert_eq!(diff, BitVec::from_bytes(&[0xe0, 0xb5]));
+        restored.apply_xor_diff(&diff);
+        assert_eq!(restored, a);
+    }
+
     #[cfg(feature = "unstable")]
     #[test]
     fn test_custom_allocator() {
@@ -0,0 +1,52 @@
+use core::prelude::rust_2021::*;
+use alloc::vec::Vec;
+
+use super::BitVec;
+
+/// Set of prefix-free code words, in which no code word is a prefix of another, such as the codes
+/// of a Huffman table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PrefixSet {
+    codes: Vec<BitVec>,
+}
+
+impl PrefixSet {
+    /// Constructs an empty set.
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns the number of code words.
+    pub fn len(&self) -> usize { self.codes.len() }
+
+    /// Returns whether the set has no code words.
+    pub fn is_empty(&self) -> bool { self.codes.is_empty() }
+
+    /// Returns an iterator over the code words in insertion order.
+    pub fn iter(&self) -> core::slice::Iter<'_, BitVec> { self.codes.iter() }
+
+    /// Inserts `code` and returns `true` if it is non-empty and is neither a prefix of an existing
+    /// code word nor has one as a prefix (including being equal to one). Otherwise, returns `false`
+    /// and leaves the set unchanged. An empty code word would match without consuming any bits.
+    pub fn insert(&mut self, code: BitVec) -> bool {
+        if code.is_empty() { return false }
+        // One is a prefix of the other iff they agree up to the length of the shorter one.
+        if self.codes.iter().any(|existing| {
+            existing.common_prefix_len(&code) == existing.len().min(code.len())
+        }) {
+            return false;
+        }
+        self.codes.push(code);
+        true
+    }
+
+    /// Returns the code word that matches the bits of `stream` starting at index `from`, along
+    /// with its length, or `None` if no code word matches. Being prefix-free, at most one code word
+    /// can match.
+    pub fn longest_match(&self, stream: &BitVec, from: usize) -> Option<(&BitVec, usize)> {
+        let remaining = stream.len().saturating_sub(from);
+        self.codes.iter()
+            .filter(|code| code.len() <= remaining &&
+                    code.iter().enumerate().all(|(i, bit)| stream[from + i] == bit))
+            .max_by_key(|code| code.len())
+            .map(|code| (code, code.len()))
+    }
+}
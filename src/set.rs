@@ -0,0 +1,53 @@
+use core::hash::{Hash, Hasher};
+use core::prelude::rust_2021::*;
+
+use super::BitVec;
+
+/// Set of indices backed by a bit vector, whose equality and hashing depend only on which bits are
+/// set. Unlike for `BitVec`, the length is ignored, so trailing 0 bits do not matter.
+#[derive(Clone, Debug, Default)]
+pub struct BitSet {
+    vec: BitVec,
+}
+
+impl BitSet {
+    /// Constructs a set of the indices of the set bits of `vec`.
+    pub fn new(vec: BitVec) -> Self { BitSet { vec } }
+
+    /// Consumes the set and returns the underlying bit vector.
+    pub fn into_bitvec(self) -> BitVec { self.vec }
+
+    /// Returns the underlying bit vector.
+    pub fn as_bitvec(&self) -> &BitVec { &self.vec }
+
+    /// Returns whether `index` is in the set, i.e. whether its bit is set.
+    pub fn contains(&self, index: usize) -> bool {
+        self.vec.get(index) == Some(true)
+    }
+
+    /// Returns the bytes of the underlying bit vector without the trailing 0 bytes, which is the
+    /// same for all bit vectors with the same set bits.
+    fn normalized_bytes(&self) -> &[u8] {
+        let bytes = self.vec.as_bytes();
+        let len = bytes.iter().rposition(|&byte| byte != 0).map_or(0, |i| i + 1);
+        &bytes[..len]
+    }
+}
+
+impl From<BitVec> for BitSet {
+    fn from(vec: BitVec) -> Self { BitSet::new(vec) }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized_bytes() == other.normalized_bytes()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized_bytes().hash(state);
+    }
+}
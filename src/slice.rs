@@ -0,0 +1,133 @@
+use core::fmt;
+use core::write;
+use core::prelude::rust_2021::*;
+
+/// Immutable view into a contiguous range of bits of a bit vector.
+///
+/// Unlike `BitVec`, the bits of a `BitSlice` need not start on a byte boundary, so no `[u8]`
+/// view is provided.
+#[derive(Clone, Copy)]
+pub struct BitSlice<'a> {
+    bytes: &'a [u8],
+    start: usize,
+    nbits: usize,
+}
+
+impl<'a> BitSlice<'a> {
+    /// Constructs a view of the `nbits` bits of `bytes` starting at bit `start`.
+    pub(crate) fn new(bytes: &'a [u8], start: usize, nbits: usize) -> Self {
+        assert!(start + nbits <= bytes.len() * 8,
+                "Expected bit range [{}, {}) within 8 x (#bytes {}).", start, start + nbits, bytes.len());
+        BitSlice { bytes, start, nbits }
+    }
+
+    /// Returns the length of the bit slice.
+    pub fn len(&self) -> usize { self.nbits }
+
+    /// Returns whether the slice is empty.
+    pub fn is_empty(&self) -> bool { self.nbits == 0 }
+
+    /// Gets the bit at the given `index`.
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index < self.len() {
+            Some(unsafe { self.get_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the bit at the given `index` without bounds checking.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than `self.len()`.
+    pub unsafe fn get_unchecked(&self, index: usize) -> bool {
+        let index = self.start + index;
+        let byte = self.bytes.get_unchecked(index / 8);
+        let pattern = 1u8 << (index % 8);
+        (*byte & pattern) != 0u8
+    }
+
+    /// Returns an iterator for the booleans in the bit slice.
+    pub fn iter(&self) -> SliceIter<'a> {
+        self.into_iter()
+    }
+}
+
+/// Allows forward iteration through the bits of a bit slice.
+#[derive(Clone)]
+pub struct SliceIter<'a> {
+    vec: BitSlice<'a>,
+    index: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for SliceIter<'a> {
+    impl_iter!();
+}
+
+impl<'a> DoubleEndedIterator for SliceIter<'a> {
+    impl_double_ended_iter!();
+}
+
+impl<'a> ExactSizeIterator for SliceIter<'a> {}
+
+impl<'a> core::iter::FusedIterator for SliceIter<'a> {}
+
+impl<'a> IntoIterator for BitSlice<'a> {
+    type Item = bool;
+    type IntoIter = SliceIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        SliceIter {
+            vec: self,
+            index: 0,
+            end: self.nbits,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &BitSlice<'a> {
+    type Item = bool;
+    type IntoIter = SliceIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        (*self).into_iter()
+    }
+}
+
+/// Iterates through the maximal runs of identical bits of a bit vector, yielding each run's value
+/// and length.
+#[derive(Clone)]
+pub struct Runs<'a> {
+    bits: BitSlice<'a>,
+    index: usize,
+}
+
+impl<'a> Runs<'a> {
+    pub(crate) fn new(bits: BitSlice<'a>) -> Self {
+        Runs { bits, index: 0 }
+    }
+}
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = (bool, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.bits.get(self.index)?;
+        let start = self.index;
+        self.index += 1;
+        while self.bits.get(self.index) == Some(value) {
+            self.index += 1;
+        }
+        Some((value, self.index - start))
+    }
+}
+
+impl<'a> fmt::Debug for BitSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BitSlice{{{:?}: {}}}", self.nbits, self)
+    }
+}
+
+impl<'a> fmt::Display for BitSlice<'a> {
+    impl_display!();
+}
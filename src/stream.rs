@@ -0,0 +1,108 @@
+use core::prelude::rust_2021::*;
+
+use super::{BitSlice, BitVec};
+
+/// Reads bits sequentially from a bit vector, starting at index 0.
+#[derive(Clone)]
+pub struct BitReader<'a> {
+    bits: BitSlice<'a>,
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(bits: BitSlice<'a>) -> Self {
+        BitReader { bits, pos: 0 }
+    }
+
+    /// Returns the index of the next bit to be read.
+    pub fn position(&self) -> usize { self.pos }
+
+    /// Returns the number of bits left to read.
+    pub fn remaining(&self) -> usize { self.bits.len() - self.pos }
+
+    /// Reads the next bit, or returns `None` if none remain.
+    pub fn read_bit(&mut self) -> Option<bool> {
+        let bit = self.bits.get(self.pos)?;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    /// Reads the next `n` bits as an integer whose least significant bit is the first bit read.
+    /// Returns `None` without advancing if fewer than `n` bits remain. Panics if `n > 64`.
+    pub fn read_bits(&mut self, n: u32) -> Option<u64> {
+        assert!(n <= 64, "Expected at most 64 bits to read, got {}.", n);
+        if (n as usize) > self.remaining() { return None }
+        let mut value = 0u64;
+        for i in 0..n {
+            if unsafe { self.bits.get_unchecked(self.pos) } {
+                value |= 1u64 << i;
+            }
+            self.pos += 1;
+        }
+        Some(value)
+    }
+}
+
+/// Reads consecutive fields of bits from a bit vector as new bit vectors, starting at index 0.
+#[derive(Clone)]
+pub struct FieldReader<'a> {
+    bits: BitSlice<'a>,
+    pos: usize,
+}
+
+impl<'a> FieldReader<'a> {
+    pub(crate) fn new(bits: BitSlice<'a>) -> Self {
+        FieldReader { bits, pos: 0 }
+    }
+
+    /// Returns the index of the next bit to be read.
+    pub fn position(&self) -> usize { self.pos }
+
+    /// Returns the number of bits left to read.
+    pub fn remaining(&self) -> usize { self.bits.len() - self.pos }
+
+    /// Reads the next `n` bits as a bit vector, or returns `None` without advancing if fewer than
+    /// `n` bits remain.
+    pub fn take(&mut self, n: usize) -> Option<BitVec> {
+        if n > self.remaining() { return None }
+        let field = self.bits.iter().skip(self.pos).take(n).collect();
+        self.pos += n;
+        Some(field)
+    }
+}
+
+/// Appends bits in sequence to the end of a bit vector.
+pub struct BitWriter<'a> {
+    vec: &'a mut BitVec,
+}
+
+impl<'a> BitWriter<'a> {
+    /// Constructs a writer that appends to `vec`.
+    pub fn new(vec: &'a mut BitVec) -> Self {
+        BitWriter { vec }
+    }
+
+    /// Returns the index of the next bit to be written, i.e. the length of the bit vector.
+    pub fn position(&self) -> usize { self.vec.len() }
+
+    /// Appends a bit.
+    pub fn write_bit(&mut self, value: bool) -> &mut Self {
+        self.vec.push(value);
+        self
+    }
+
+    /// Appends the `n` low bits of `value`, least significant first. Panics if `n > 64`.
+    pub fn write_bits(&mut self, value: u64, n: u32) -> &mut Self {
+        self.vec.push_bits(value, n);
+        self
+    }
+
+    /// Pads with `value` up to the next multiple of 8 bits.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn align(&mut self, value: bool) -> &mut Self {
+        while self.vec.len() % 8 != 0 {
+            self.vec.push(value);
+        }
+        self
+    }
+}